@@ -0,0 +1,121 @@
+//! Command-line calculator that drives the same `Calc` as the web page.
+//!
+//! ```text
+//! webcalc                       read equations from standard input, one per line
+//! webcalc --eval "2 + 3 * 4"    print one answer and exit
+//! webcalc --notation postfix    read postfix notation instead of infix
+//! ```
+//!
+//! Errors are written to standard error. The exit code is 1 when any equation gave an error and
+//! 2 when the arguments are wrong.
+// Explicit returns, as in the library.
+#![allow(clippy::needless_return)]
+
+extern crate webcalc;
+
+use std::io::{BufRead, IsTerminal, Write};
+use std::process;
+use webcalc::{Calc, Notation};
+
+const USAGE: &str = "Usage: webcalc [--notation infix|prefix|postfix] [--eval EQUATION]...";
+
+fn main() {
+    let mut calc = Calc::new();
+    let mut equations = Vec::<String>::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let (flag, value) = match arg.find('=') {
+            Some(position) if arg.starts_with("--") => (arg[..position].to_string(), Some(arg[position + 1..].to_string())),
+            _ => (arg.clone(), None),
+        };
+        match flag.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "-e" | "--eval" => match value.or_else(|| args.next()) {
+                Some(equation) => equations.push(equation),
+                None => usage_error("--eval needs an equation."),
+            },
+            "-n" | "--notation" => {
+                let name = value.or_else(|| args.next()).unwrap_or_default();
+                match parse_notation(&name) {
+                    Some(notation) => calc.set_notation(notation),
+                    None => usage_error(&format!("Unknown notation '{}'; use infix, prefix or postfix.", name)),
+                }
+            }
+            _ => usage_error(&format!("Unknown argument '{}'.", arg)),
+        }
+    }
+
+    if !equations.is_empty() {
+        for equation in equations.iter() {
+            if !run(&mut calc, equation) {
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    process::exit(repl(&mut calc));
+}
+
+/// Reads equations from standard input until it ends, showing a prompt when a person is typing.
+/// Returns the exit code.
+fn repl(calc: &mut Calc) -> i32 {
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut ret = 0;
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            print!("> ");
+            std::io::stdout().flush().unwrap_or(());
+        }
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(e)) => {
+                eprintln!("Error reading input: {}", e);
+                return 1;
+            }
+            None => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !run(calc, &line) {
+            ret = 1;
+        }
+    }
+    if interactive {
+        println!();
+    }
+    return ret;
+}
+
+/// Evaluates one equation, printing the answer or the error. Returns whether it succeeded.
+fn run(calc: &mut Calc, equation: &str) -> bool {
+    match calc.evaluate(equation) {
+        Ok(entry) => {
+            println!("{}", entry.answer);
+            return true;
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return false;
+        }
+    }
+}
+
+fn parse_notation(name: &str) -> Option<Notation> {
+    match name {
+        "infix" => return Some(Notation::Infix),
+        "prefix" => return Some(Notation::Prefix),
+        "postfix" => return Some(Notation::Postfix),
+        _ => return None,
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    process::exit(2);
+}
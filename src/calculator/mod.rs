@@ -1,21 +1,26 @@
+#![allow(clippy::needless_return)]
+
 use regex::Regex;
+use statistics;
 use std::collections::HashMap;
+use value;
 
 /// Enum that defines parts of an equation: 
-/// * An Operator [+, -, *, /]
+/// * An Operator [+, -, *, /, ^, =, ==, !=, <, <=, >, >=, and, or, in]
 /// * A Variable [Any non-numeric and not an oerator. Including whole words.]
 /// * A Value [Any numeric. e.g. 1, 2, 3.141414...]
+/// * A Function [An operator or built-in function applied to the given number of arguments.]
 /// ```
 #[derive(Debug, Clone)]
 pub enum Cell {
     Operator(String),
     Variable(String),
     Value(f64),
+    Function(String, usize),
 }
 
 /// Function that parses an equation in prefix notation to either a vector of cells (an equation) or a hashmap.
 pub fn parse_to_vec_and_map(in_str: &str) -> Result<(Vec<Cell>, HashMap<String, f64>), String> {
-    let mut vec = Vec::<Cell>::new();
     let mut map = HashMap::<String, f64>::new();
     let whitespace = Regex::new(r"^\s+?").unwrap();
 
@@ -23,111 +28,320 @@ pub fn parse_to_vec_and_map(in_str: &str) -> Result<(Vec<Cell>, HashMap<String,
         .split_whitespace()
         .filter(|x| !whitespace.is_match(x))
         .peekable();
-    loop {
-        let item = word_or_punctuation.next();
-        match item {
-            Some("=") => {
-                let variable = word_or_punctuation.next().unwrap();
-                let value = word_or_punctuation.next().unwrap().parse::<f64>();
-                if word_or_punctuation.next() == None {
-                    if variable.is_empty() || value.is_err() {
-                        return Err(
-                            String::from("Equals can only take the form of variable = value.")
-                        );
-                    } else {
-                        map.insert(String::from(variable), value.unwrap());
-                    }
-                } else {
+    if word_or_punctuation.peek() == Some(&"=") {
+        word_or_punctuation.next();
+        let variable = word_or_punctuation.next().unwrap_or("");
+        let value = word_or_punctuation.next().unwrap_or("").parse::<f64>();
+        if word_or_punctuation.next().is_none() {
+            match value {
+                Ok(value) if !variable.is_empty() => {
+                    map.insert(String::from(variable), value);
+                }
+                _ => {
                     return Err(
-                        String::from("Equals can only take the form of variable = value. Cannot solve for value.")
+                        String::from("Equals can only take the form of variable = value.")
                     );
                 }
             }
-            Some("(") | Some(")") => {}
-            Some("+") | Some("-") | Some("*") | Some("/") => {
-                let s = String::from(item.unwrap());
-                let cell_item = Cell::Operator(s);
-                vec.push(cell_item);
-            }
-            Some(_) => {
-                let val = item.unwrap().parse::<f64>();
-                if val.is_err() {
-                    let s = String::from(item.unwrap());
-                    let cell_item = Cell::Variable(s);
-                    vec.push(cell_item);
-                } else {
-                    let cell_item = Cell::Value(val.unwrap());
-                    vec.push(cell_item);
+        } else {
+            return Err(
+                String::from("Equals can only take the form of variable = value. To solve for a variable use solve(equation, variable).")
+            );
+        }
+        return Ok((Vec::<Cell>::new(), map));
+    }
+
+    let vec = parse_to_vec(in_str)?;
+    return Ok((vec, map));
+}
+
+/// Function that parses a list of numbers pasted from a spreadsheet column or row. The numbers
+/// may be separated by new lines, tabs, spaces, commas or semicolons.
+/// # Example
+/// 1.5\n2\n3 -> [1.5, 2, 3]
+/// ...
+pub fn parse_list(in_str: &str) -> Result<Vec<f64>, String> {
+    let mut ret = Vec::<f64>::new();
+    for item in in_str.split(|c: char| c.is_whitespace() || c == ',' || c == ';') {
+        if item.is_empty() {
+            continue;
+        }
+        match item.parse::<f64>() {
+            Ok(val) => ret.push(val),
+            Err(_) => return Err(format!("'{}' is not a number.", item)),
+        }
+    }
+    if ret.is_empty() {
+        return Err(String::from("No numbers found."));
+    }
+    return Ok(ret);
+}
+
+/// Function that parses an equation in prefix notation to a vector of cells.
+/// Outside of parentheses every operator takes two arguments. A parenthesized group that starts
+/// with an operator or a built-in function is an argument list, so operators and functions can
+/// take any number of arguments.
+/// # Example
+/// (+ 1 2 3 4) -> 10
+/// (max a b c)
+/// ...
+pub fn parse_to_vec(in_str: &str) -> Result<Vec<Cell>, String> {
+    let spaced = in_str.replace("(", " ( ").replace(")", " ) ");
+    let words = spaced.split_whitespace().collect::<Vec<&str>>();
+    let mut vec = Vec::<Cell>::new();
+    let mut index = 0;
+    while index < words.len() {
+        index = parse_item(&words, index, &mut vec)?;
+    }
+    return Ok(vec);
+}
+
+/// Private function that parses one complete prefix sub-equation starting at `index`, returning
+/// the index of the first word after it.
+fn parse_item(words: &[&str], index: usize, vec: &mut Vec<Cell>) -> Result<usize, String> {
+    match words[index] {
+        "(" => {
+            return parse_group(words, index + 1, vec);
+        }
+        ")" => {
+            return Err(String::from("Unexpected closing parenthesis."));
+        }
+        item if is_operator(item) => {
+            vec.push(Cell::Operator(String::from(item)));
+            return parse_arguments(words, index, 2, vec);
+        }
+        item if is_function(item) => {
+            // Without an argument list a variadic function is binary, like the operators.
+            let count = get_function_arity(item).1.unwrap_or(2);
+            vec.push(Cell::Function(String::from(item), count));
+            return parse_arguments(words, index, count, vec);
+        }
+        item => {
+            match item.parse::<f64>() {
+                Ok(val) => vec.push(Cell::Value(val)),
+                Err(_) => {
+                    // A signed name, e.g. -x, is the negation of that variable.
+                    if item.len() > 1 && item.starts_with('-') {
+                        vec.push(Cell::Function(String::from("-"), 1));
+                        vec.push(Cell::Variable(String::from(&item[1..])));
+                    } else {
+                        vec.push(Cell::Variable(String::from(item)));
+                    }
                 }
             }
-            None => break,
+            return Ok(index + 1);
         }
     }
-    return Ok((vec, map));
+}
+
+/// Private function that parses the `count` sub-equations following the operator or function at `index`.
+fn parse_arguments(words: &[&str], index: usize, count: usize, vec: &mut Vec<Cell>) -> Result<usize, String> {
+    let mut next = index + 1;
+    for _ in 0..count {
+        if next >= words.len() || words[next] == ")" {
+            if is_function(words[index]) {
+                return Err(format!("Not enough values to apply to Function {}.", words[index]));
+            }
+            return Err(format!("Not enough values to apply to Operator {}.", words[index]));
+        }
+        next = parse_item(words, next, vec)?;
+    }
+    return Ok(next);
+}
+
+/// Private function that parses the inside of a parenthesized group starting at `index`, returning
+/// the index of the first word after the closing parenthesis.
+fn parse_group(words: &[&str], index: usize, vec: &mut Vec<Cell>) -> Result<usize, String> {
+    if index >= words.len() {
+        return Err(String::from("Missing closing parenthesis."));
+    }
+    let head = words[index];
+    if head == ")" {
+        return Err(String::from("Empty parentheses."));
+    }
+    let is_list = is_operator(head) || is_function(head);
+    if !is_list {
+        let next = parse_item(words, index, vec)?;
+        if next >= words.len() {
+            return Err(String::from("Missing closing parenthesis."));
+        }
+        if words[next] != ")" {
+            if head.parse::<f64>().is_err() && next == index + 1 {
+                return Err(format!("Unknown function {}.", head));
+            }
+            return Err(String::from("Too many values inside parentheses."));
+        }
+        return Ok(next + 1);
+    }
+
+    let mut arguments = Vec::<Cell>::new();
+    let mut count = 0;
+    let mut next = index + 1;
+    loop {
+        if next >= words.len() {
+            return Err(String::from("Missing closing parenthesis."));
+        }
+        if words[next] == ")" {
+            break;
+        }
+        next = parse_item(words, next, &mut arguments)?;
+        count += 1;
+    }
+    if is_operator(head) && count == 2 {
+        vec.push(Cell::Operator(String::from(head)));
+    } else {
+        check_arity(head, count)?;
+        vec.push(Cell::Function(String::from(head), count));
+    }
+    vec.append(&mut arguments);
+    return Ok(next + 1);
+}
+
+/// Function that reports whether a word is a binary operator.
+pub fn is_operator(word: &str) -> bool {
+    match word {
+        "=" | "+" | "-" | "*" | "/" | "^" => return true,
+        word => return is_comparison(word) || word == "and" || word == "or" || word == "in",
+    }
+}
+
+/// Function that reports whether an operator compares two values, giving true or false.
+pub fn is_comparison(op: &str) -> bool {
+    match op {
+        "==" | "!=" | "<" | "<=" | ">" | ">=" => return true,
+        _ => return false,
+    }
+}
+
+/// Function that gives the value of a named number, pi or e. These cannot be set as variables.
+pub fn number_constant(name: &str) -> Option<f64> {
+    match name {
+        "pi" => return Some(std::f64::consts::PI),
+        "e" => return Some(std::f64::consts::E),
+        _ => return None,
+    }
+}
+
+/// Function that reports whether a name is a built-in function.
+pub fn is_function(name: &str) -> bool {
+    return !is_operator(name) && get_function_arity(name).1 != Some(0);
+}
+
+/// Function that returns the smallest and largest number of arguments an operator or a built-in
+/// function accepts. A largest of None means any number; a largest of Some(0) means the name is
+/// not a function at all.
+pub fn get_function_arity(name: &str) -> (usize, Option<usize>) {
+    match name {
+        "+" | "-" | "*" | "/" | "max" | "min" | "list" => return (1, None),
+        "and" | "or" => return (2, None),
+        op if is_comparison(op) || op == "in" => return (2, Some(2)),
+        "not" => return (1, Some(1)),
+        "if" => return (3, Some(3)),
+        "mean" | "median" | "mode" | "stdev" | "pstdev" | "var" | "pvar" | "sum" | "count" => return (1, None),
+        "percentile" => return (2, None),
+        "^" | "diff" => return (2, Some(2)),
+        "solve" | "index" => return (2, Some(3)),
+        "dot" | "cross" => return (2, Some(2)),
+        "transpose" | "det" | "inv" => return (1, Some(1)),
+        "integrate" | "product" => return (4, Some(4)),
+        "abs" | "sqrt" | "exp" | "ln" | "log" | "sin" | "cos" | "tan" | "asin" | "acos" | "atan" => {
+            return (1, Some(1));
+        }
+        _ => return (0, Some(0)),
+    }
+}
+
+/// Function that checks the number of arguments given to an operator or built-in function.
+pub fn check_arity(name: &str, count: usize) -> Result<(), String> {
+    let (least, most) = get_function_arity(name);
+    if most == Some(0) {
+        return Err(format!("Unknown function {}.", name));
+    }
+    if count < least || most.is_some_and(|most| count > most) {
+        if most == Some(least) {
+            return Err(format!("Function {} takes {} argument(s), not {}.", name, least, count));
+        }
+        return Err(format!("Function {} takes at least {} argument(s), not {}.", name, least, count));
+    }
+    return Ok(());
 }
 
 /// Function that attempts to resolve an equation, using the passed in hash map to resolve variables.
+/// Equations that build vectors or hold integrate, sum or product calls are handed to
+/// `value::calculate`, which evaluates them as a tree; their answer must still be a number.
 pub fn calculate(vec0: &Vec<Cell>, map: &HashMap<String, f64>) -> Result<Vec<Cell>, String> {
+    if value::needs_tree(vec0) {
+        return Ok(vec![Cell::Value(value::calculate(vec0, map)?.number()?)]);
+    }
     let mut ret = Vec::<Cell>::new();
     let mut stack_ops = Vec::new();
     let mut stack_vals = Vec::new();
-    let mut iter = vec0.iter().rev();
 
-    loop {
-        match iter.next() {
-            Some(cell) => match cell.clone() {
-                Cell::Operator(ref val) => {
-                    stack_ops.push(val.clone());
-                    if stack_vals.len() >= 2 {
-                        let cell1 = stack_vals.pop().unwrap();
-                        let cell2 = stack_vals.pop().unwrap();
-                        let op = stack_ops.pop().unwrap();
-                        let cell_answer = process(&op, &cell1, &cell2);
-                        if cell_answer.is_ok() {
-                            if let Cell::Value(ref i) = cell_answer.ok().unwrap() {
-                                stack_vals.push(*i);
-                            }
-                        } else {
-                            return Err(cell_answer.err().unwrap());
+    for cell in vec0.iter().rev() {
+        match cell.clone() {
+            Cell::Operator(ref val) => {
+                stack_ops.push(val.clone());
+                if stack_vals.len() >= 2 {
+                    let cell1 = stack_vals.pop().unwrap();
+                    let cell2 = stack_vals.pop().unwrap();
+                    let op = stack_ops.pop().unwrap();
+                    let cell_answer = process(&op, &cell1, &cell2);
+                    if cell_answer.is_ok() {
+                        if let Cell::Value(ref i) = cell_answer.ok().unwrap() {
+                            stack_vals.push(*i);
                         }
                     } else {
-                        return Err(format!("Not enough values to apply to Operator {}.", val));
+                        return Err(cell_answer.err().unwrap());
                     }
+                } else {
+                    return Err(format!("Not enough values to apply to Operator {}.", val));
+                }
+            }
+            Cell::Function(ref name, ref count) => {
+                if stack_vals.len() < *count {
+                    return Err(format!("Not enough values to apply to Function {}.", name));
                 }
-                Cell::Value(ref val) => {
-                    stack_vals.push(*val);
+                let mut args = Vec::<f64>::new();
+                for _ in 0..*count {
+                    args.push(stack_vals.pop().unwrap());
                 }
-                Cell::Variable(ref val) => {
-                    if map.contains_key(val) {
-                        match map.get(val) {
-                            Some(v) => {
-                                stack_vals.push(*v);
-                            }
-                            None => {
-                                return Err(format!("Variable {} has a defined value of None.", val));
-                            }
+                stack_vals.push(process_function(name, &args)?);
+            }
+            Cell::Value(ref val) => {
+                stack_vals.push(*val);
+            }
+            Cell::Variable(ref val) => {
+                if map.contains_key(val) {
+                    match map.get(val) {
+                        Some(v) => {
+                            stack_vals.push(*v);
+                        }
+                        None => {
+                            return Err(format!("Variable {} has a defined value of None.", val));
                         }
-                    } else {
-                        return Err(format!("Variable {} does not have a defined value.", val));
                     }
+                } else if let Some(constant) = number_constant(val) {
+                    stack_vals.push(constant);
+                } else {
+                    return Err(format!("Variable {} does not have a defined value.", val));
                 }
-            },
-            None => {
-                break;
             }
         }
     }
 
-    if stack_ops.len() > 0 {
+    if !stack_ops.is_empty() {
         return Err(String::from("Did not use all operators!"));
     }
 
-    ret.push(Cell::Value(stack_vals.pop().unwrap()));
+    match stack_vals.pop() {
+        Some(val) => ret.push(Cell::Value(val)),
+        None => return Err(String::from("Nothing to calculate.")),
+    }
     return Ok(ret);
 }
 
 /// Function that converts a vector of cells into a prefix equation.
-pub fn convert_cell_vector_to_string(vec: &Vec<Cell>) -> String {
+pub fn convert_cell_vector_to_string(vec: &[Cell]) -> String {
     let mut ret = String::new();
     let mut first = true;
 
@@ -136,7 +350,7 @@ pub fn convert_cell_vector_to_string(vec: &Vec<Cell>) -> String {
         if first {
             first = false;
         } else {
-            ret.push_str(" ");
+            ret.push(' ');
         }
 
         if let Cell::Value(ref ret_val) = cell_clone {
@@ -145,6 +359,8 @@ pub fn convert_cell_vector_to_string(vec: &Vec<Cell>) -> String {
             ret.push_str(format!("{:.8}", ret_val).as_str());
         } else if let Cell::Operator(ref ret_val) = cell_clone {
             ret.push_str(format!("{:.8}", ret_val).as_str());
+        } else if let Cell::Function(ref ret_val, _) = cell_clone {
+            ret.push_str(format!("{:.8}", ret_val).as_str());
         } else {
             ret.push_str("error");
         }
@@ -152,8 +368,8 @@ pub fn convert_cell_vector_to_string(vec: &Vec<Cell>) -> String {
     return ret;
 }
 
-/// Private function that performs the defined set of calculation functions (+, -, *, /)
-fn process(op: &String, left: &f64, right: &f64) -> Result<Cell, String> {
+/// Function that performs the defined set of calculation functions (+, -, *, /, ^)
+pub fn process(op: &String, left: &f64, right: &f64) -> Result<Cell, String> {
     match op.as_str() {
         "+" => {
             let cell_item = Cell::Value(left + right);
@@ -171,16 +387,73 @@ fn process(op: &String, left: &f64, right: &f64) -> Result<Cell, String> {
             let cell_item = Cell::Value(left / right);
             return Ok(cell_item);
         }
+        "^" => {
+            let cell_item = Cell::Value(left.powf(*right));
+            return Ok(cell_item);
+        }
         _ => {
             return Err(format!("Cannot process: ({}{}{})", op, left, right));
         }
     }
 }
 
+/// Function that applies an operator or a built-in function to a list of arguments. Operators
+/// given more than two arguments fold from the left; given one, '-' negates and '/' inverts.
+pub fn process_function(name: &str, args: &[f64]) -> Result<f64, String> {
+    check_arity(name, args.len())?;
+    match name {
+        "+" => return Ok(args.iter().sum()),
+        "*" => return Ok(args.iter().product()),
+        "-" if args.len() == 1 => return Ok(-args[0]),
+        "/" if args.len() == 1 => return Ok(1.0 / args[0]),
+        "-" | "/" | "^" => {
+            let mut ret = args[0];
+            for arg in args[1..].iter() {
+                if let Cell::Value(val) = process(&String::from(name), &ret, arg)? {
+                    ret = val;
+                }
+            }
+            return Ok(ret);
+        }
+        "max" => return Ok(args.iter().cloned().fold(f64::NEG_INFINITY, f64::max)),
+        "min" => return Ok(args.iter().cloned().fold(f64::INFINITY, f64::min)),
+        "abs" => return Ok(args[0].abs()),
+        "sqrt" => return Ok(args[0].sqrt()),
+        "exp" => return Ok(args[0].exp()),
+        "ln" => return Ok(args[0].ln()),
+        "log" => return Ok(args[0].log10()),
+        "sin" => return Ok(args[0].sin()),
+        "cos" => return Ok(args[0].cos()),
+        "tan" => return Ok(args[0].tan()),
+        "asin" => return Ok(args[0].asin()),
+        "acos" => return Ok(args[0].acos()),
+        "atan" => return Ok(args[0].atan()),
+        "diff" => return Err(String::from("Function diff gives an equation, not a value.")),
+        "solve" => return Err(String::from("Function solve gives a list of roots, not a value.")),
+        "list" => return Err(String::from("Function list gives a list, not a value.")),
+        "dot" | "cross" | "transpose" | "det" | "inv" | "index" => {
+            return Err(format!("Function {} needs a vector or a matrix.", name));
+        }
+        "integrate" | "product" => {
+            return Err(format!("Function {} needs its equation, not a value.", name));
+        }
+        name if statistics::is_statistic(name) => return statistics::process_statistic(name, args),
+        _ => return Err(format!("Unknown function {}.", name)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(Ok(vec![1.5, 2.0, 3.0]), parse_list("1.5\n2\r\n3\n"));
+        assert_eq!(Ok(vec![1.0, 2.0, 3.0, 4.0]), parse_list("1\t2; 3, 4"));
+        assert_eq!(Err(String::from("'x' is not a number.")), parse_list("1\nx"));
+        assert_eq!(Err(String::from("No numbers found.")), parse_list(" \n"));
+    }
+
     #[test]
     fn test_simple_add() {
         let result = String::from("4.00000000");
@@ -289,6 +562,52 @@ mod tests {
         }
     }
 
+    fn evaluate(input: &str) -> Result<String, String> {
+        let (vec, map) = parse_to_vec_and_map(input)?;
+        let result_cal = calculate(&vec, &map)?;
+        return Ok(convert_cell_vector_to_string(&result_cal));
+    }
+
+    #[test]
+    fn test_variadic_operators() {
+        assert_eq!(Ok(String::from("10.00000000")), evaluate("(+ 1 2 3 4)"));
+        assert_eq!(Ok(String::from("24.00000000")), evaluate("(* 1 2 3 4)"));
+        assert_eq!(Ok(String::from("-8.00000000")), evaluate("(- 1 2 3 4)"));
+        assert_eq!(Ok(String::from("-5.00000000")), evaluate("(- 5)"));
+        assert_eq!(Ok(String::from("0.25000000")), evaluate("(/ 4)"));
+        assert_eq!(Ok(String::from("12.00000000")), evaluate("* (+ 1 2 3) (- 4 1 1)"));
+    }
+
+    #[test]
+    fn test_variadic_functions() {
+        assert_eq!(Ok(String::from("9.00000000")), evaluate("(max 1 9 3 (+ 2 2))"));
+        assert_eq!(Ok(String::from("-2.00000000")), evaluate("(min 4 -2)"));
+        assert_eq!(Ok(String::from("3.00000000")), evaluate("(sqrt 9)"));
+        assert_eq!(Ok(String::from("5.00000000")), evaluate("+ sqrt 9 2"));
+    }
+
+    #[test]
+    fn test_grouping_still_nests() {
+        assert_eq!(Ok(String::from("7.00000000")), evaluate("( + 1 * 2 3 )"));
+    }
+
+    #[test]
+    fn test_mismatched_groups() {
+        assert_eq!(Err(String::from("Missing closing parenthesis.")), evaluate("(+ 1 2"));
+        assert_eq!(Err(String::from("Unexpected closing parenthesis.")), evaluate("+ 1 2)"));
+        assert_eq!(Err(String::from("Empty parentheses.")), evaluate("+ 1 ()"));
+        assert_eq!(Err(String::from("Too many values inside parentheses.")), evaluate("(1 2)"));
+        assert_eq!(Err(String::from("Unknown function f.")), evaluate("(f 1 2)"));
+        assert_eq!(
+            Err(String::from("Function sin takes 1 argument(s), not 2.")),
+            evaluate("(sin 1 2)")
+        );
+        assert_eq!(
+            Err(String::from("Not enough values to apply to Operator +.")),
+            evaluate("+ 1")
+        );
+    }
+
     #[test]
     fn test_substitution_1() {
         let result = String::from("10.00000000");
@@ -0,0 +1,209 @@
+#![allow(clippy::needless_return)]
+
+use calculator;
+use date;
+use std::collections::HashMap;
+use value;
+use wasm_bindgen::prelude::*;
+
+/// Built-in functions that can be completed, with how they are called. Operators, and list and
+/// index which are written with brackets, are left out.
+const FUNCTIONS: [(&str, &str); 34] = [
+    ("abs", "abs(x)"),
+    ("acos", "acos(x)"),
+    ("asin", "asin(x)"),
+    ("atan", "atan(x)"),
+    ("cos", "cos(x)"),
+    ("count", "count(values, ...)"),
+    ("cross", "cross(u, v)"),
+    ("det", "det(m)"),
+    ("diff", "diff(f, x)"),
+    ("dot", "dot(u, v)"),
+    ("exp", "exp(x)"),
+    ("if", "if(condition, then, otherwise)"),
+    ("integrate", "integrate(f, x, from, to)"),
+    ("inv", "inv(m)"),
+    ("ln", "ln(x)"),
+    ("log", "log(x)"),
+    ("max", "max(a, ...)"),
+    ("mean", "mean(values, ...)"),
+    ("median", "median(values, ...)"),
+    ("min", "min(a, ...)"),
+    ("mode", "mode(values, ...)"),
+    ("not", "not x"),
+    ("percentile", "percentile(values, ..., p)"),
+    ("product", "product(f, k, from, to)"),
+    ("pstdev", "pstdev(values, ...)"),
+    ("pvar", "pvar(values, ...)"),
+    ("sin", "sin(x)"),
+    ("solve", "solve(equation, x, guess)"),
+    ("sqrt", "sqrt(x)"),
+    ("stdev", "stdev(values, ...)"),
+    ("sum", "sum(values, ...) or sum(f, k, from, to)"),
+    ("tan", "tan(x)"),
+    ("transpose", "transpose(m)"),
+    ("var", "var(values, ...)"),
+];
+
+/// Named constants that are not variables: pi and e, the booleans, the current time and units of
+/// time.
+const CONSTANTS: [&str; 15] = [
+    "pi", "e", "true", "false", "now", "second", "seconds", "minute", "minutes", "hour", "hours", "day", "days", "week", "weeks",
+];
+
+/// Struct that holds one way to complete the name being typed: the name, its kind (variable,
+/// list, constant or function), a detail to show with it (a value or how a function is called)
+/// and where the typed part of the name starts, counted in characters.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    name: String,
+    kind: &'static str,
+    detail: String,
+    start: usize,
+}
+
+#[wasm_bindgen]
+impl Completion {
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        return self.name.clone();
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        return String::from(self.kind);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn detail(&self) -> String {
+        return self.detail.clone();
+    }
+
+    /// Position of the first character the name replaces; the last is just before the caret.
+    #[wasm_bindgen(getter)]
+    pub fn start(&self) -> usize {
+        return self.start;
+    }
+}
+
+impl Completion {
+    /// Function that returns the text to type in place of what was typed: the name, followed by
+    /// an opening bracket for a function that takes one.
+    pub fn text(&self) -> String {
+        if self.kind == "function" && self.name != "not" {
+            return format!("{}(", self.name);
+        }
+        return self.name.clone();
+    }
+}
+
+/// Function that finds the names starting with the part of a name that ends at the caret:
+/// variables first, then lists, constants and functions, each in alphabetical order. Nothing is
+/// offered when the caret does not follow a name or the name is already complete and unique.
+/// # Example
+/// "2 * si", 6 -> sin (function, sin(x))
+/// ...
+pub fn complete(input: &str, caret: usize, variables: &HashMap<String, f64>, lists: &HashMap<String, Vec<f64>>) -> Vec<Completion> {
+    let chars = input.chars().collect::<Vec<char>>();
+    let caret = caret.min(chars.len());
+    let mut start = caret;
+    while start > 0 && (chars[start - 1].is_alphanumeric() || chars[start - 1] == '_') {
+        start -= 1;
+    }
+    // A name starts with a letter, so 2pi completes pi and 12 completes nothing.
+    while start < caret && !(chars[start].is_alphabetic() || chars[start] == '_') {
+        start += 1;
+    }
+    if start == caret {
+        return Vec::new();
+    }
+    let typed = chars[start..caret].iter().collect::<String>();
+    let mut ret = Vec::<Completion>::new();
+    let mut add = |name: &str, kind: &'static str, detail: String| {
+        if name.starts_with(&typed) {
+            ret.push(Completion { name: String::from(name), kind, detail, start });
+        }
+    };
+    let mut names = variables.keys().collect::<Vec<&String>>();
+    names.sort();
+    for name in names {
+        add(name, "variable", value::print_value(&value::Value::Number(variables[name])));
+    }
+    let mut names = lists.keys().collect::<Vec<&String>>();
+    names.sort();
+    for name in names {
+        add(name, "list", format!("{} numbers", lists[name].len()));
+    }
+    for name in CONSTANTS.iter() {
+        let detail = match *name {
+            "true" | "false" => String::from("boolean"),
+            "now" => String::from("the current time"),
+            "pi" | "e" => value::print_value(&value::Value::Number(calculator::number_constant(name).unwrap())),
+            unit => date::print_duration(date::unit_seconds(unit).unwrap()),
+        };
+        add(name, "constant", detail);
+    }
+    for (name, signature) in FUNCTIONS.iter() {
+        add(name, "function", String::from(*signature));
+    }
+    ret.sort_by_key(|completion| (kind_order(completion.kind), completion.name.clone()));
+    if ret.len() == 1 && ret[0].name == typed {
+        return Vec::new();
+    }
+    return ret;
+}
+
+fn kind_order(kind: &str) -> usize {
+    match kind {
+        "variable" => return 0,
+        "list" => return 1,
+        "constant" => return 2,
+        _ => return 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(input: &str, caret: usize) -> Vec<String> {
+        let mut variables = HashMap::<String, f64>::new();
+        variables.insert(String::from("size"), 2.0);
+        let mut lists = HashMap::<String, Vec<f64>>::new();
+        lists.insert(String::from("samples"), vec![1.0, 2.0]);
+        return complete(input, caret, &variables, &lists).iter().map(|completion| completion.name()).collect();
+    }
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(vec!["size", "samples", "second", "seconds", "sin", "solve", "sqrt", "stdev", "sum"], names("2 * s", 5));
+        assert_eq!(vec!["size", "sin"], names("2 * si", 6));
+        assert_eq!(vec!["pi", "percentile", "product", "pstdev", "pvar"], names("2p", 2));
+        assert_eq!(Vec::<String>::new(), names("12", 2));
+        assert_eq!(Vec::<String>::new(), names("2 + ", 4));
+        assert_eq!(Vec::<String>::new(), names("sqrt", 4));
+        assert_eq!(vec!["day", "days"], names("da + 1", 2));
+    }
+
+    #[test]
+    fn test_details() {
+        let mut variables = HashMap::<String, f64>::new();
+        variables.insert(String::from("size"), 2.0);
+        let completions = complete("x = si", 6, &variables, &HashMap::new());
+        assert_eq!(("size", "variable", "2.00000000", 4), (completions[0].name().as_str(), completions[0].kind, completions[0].detail.as_str(), completions[0].start()));
+        assert_eq!(("sin", "function", "sin(x)"), (completions[1].name().as_str(), completions[1].kind, completions[1].detail.as_str()));
+        assert_eq!("sin(", completions[1].text());
+        assert_eq!("7 days", complete("wee", 3, &variables, &HashMap::new())[0].detail());
+    }
+
+    #[test]
+    fn test_functions_are_built_in() {
+        for (name, _) in FUNCTIONS.iter() {
+            assert!(calculator::is_function(name), "{}", name);
+        }
+        for name in CONSTANTS.iter() {
+            assert!(value::is_constant(name), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,346 @@
+#![allow(clippy::needless_return)]
+
+use wasm_bindgen::prelude::*;
+
+/// Number of seconds in a day.
+const DAY: f64 = 86400.0;
+
+/// Names of the months, January first.
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+/// Enum that selects how dates are shown.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateFormat {
+    /// As date literals are written, e.g. 2026-10-18T09:30.
+    Iso,
+    /// Day, name of the month and year, e.g. 18 October 2026 09:30.
+    Long,
+    /// Month/day/year with a 12-hour clock, e.g. 10/18/2026 9:30 AM.
+    Us,
+}
+
+/// Enum that selects how durations are shown.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DurationFormat {
+    /// Named parts, e.g. 1 day 2 hours 30 minutes.
+    Words,
+    /// Abbreviated parts, e.g. 1d 2h 30m.
+    Short,
+    /// Hours, minutes and seconds, e.g. 26:30:00.
+    Clock,
+}
+
+/// Struct that holds how dates and durations are shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Formats {
+    pub date: DateFormat,
+    pub duration: DurationFormat,
+}
+
+impl Default for Formats {
+    fn default() -> Formats {
+        return Formats {
+            date: DateFormat::Iso,
+            duration: DurationFormat::Words,
+        };
+    }
+}
+
+/// Function that reads a date literal, with an optional time of day, as seconds since
+/// 1970-01-01 in UTC. Returns None when the text is not a date literal or names a day that does
+/// not exist.
+/// # Example
+/// 2026-10-18 -> 1792281600
+/// 2026-10-18T09:30 -> 1792315800
+/// ...
+pub fn parse_date(text: &str) -> Option<f64> {
+    let (day, time) = match text.find('T') {
+        Some(position) => (&text[..position], Some(&text[position + 1..])),
+        None => (text, None),
+    };
+    let parts = split_digits(day, '-', &[4, 2, 2])?;
+    let (year, month, date) = (parts[0], parts[1], parts[2]);
+    if !(1..=12).contains(&month) || date < 1 || date > days_in_month(year, month) {
+        return None;
+    }
+    let mut ret = days_from_civil(year, month, date) as f64 * DAY;
+    if let Some(time) = time {
+        let parts = split_digits(time, ':', &[2, 2]).or_else(|| split_digits(time, ':', &[2, 2, 2]))?;
+        let seconds = parts.get(2).cloned().unwrap_or(0);
+        if parts[0] > 23 || parts[1] > 59 || seconds > 59 {
+            return None;
+        }
+        ret += (parts[0] * 3600 + parts[1] * 60 + seconds) as f64;
+    }
+    return Some(ret);
+}
+
+/// Function that reports whether a word has the shape of a date literal, e.g. 2026-10-18 or
+/// 2026-10-18T09:30, whether or not the day exists.
+pub fn is_date_literal(text: &str) -> bool {
+    let day = text.split('T').next().unwrap();
+    return split_digits(day, '-', &[4, 2, 2]).is_some();
+}
+
+/// Function that checks that a date worked out by arithmetic can be written as a date literal,
+/// i.e. that it falls in the years 0000 to 9999.
+pub fn check_date(seconds: f64) -> Result<f64, String> {
+    let first = days_from_civil(0, 1, 1) as f64 * DAY;
+    let last = days_from_civil(10000, 1, 1) as f64 * DAY;
+    if !(seconds >= first && seconds < last) {
+        return Err(String::from("The date is out of range; dates run from 0000-01-01 to 9999-12-31."));
+    }
+    return Ok(seconds);
+}
+
+/// Function that prints a date in the form it is written, leaving off a time of midnight.
+/// # Example
+/// 1792281600 -> 2026-10-18
+/// 1792315800 -> 2026-10-18T09:30
+/// ...
+pub fn print_date(seconds: f64) -> String {
+    let seconds = seconds.round() as i64;
+    let days = seconds.div_euclid(86400);
+    let time = seconds.rem_euclid(86400);
+    let (year, month, date) = civil_from_days(days);
+    let mut ret = format!("{:04}-{:02}-{:02}", year, month, date);
+    if time != 0 {
+        ret.push_str(&format!("T{:02}:{:02}", time / 3600, time % 3600 / 60));
+        if time % 60 != 0 {
+            ret.push_str(&format!(":{:02}", time % 60));
+        }
+    }
+    return ret;
+}
+
+/// Function that prints a duration in days, hours, minutes and seconds, leaving out the parts
+/// that are zero.
+/// # Example
+/// 3801600 -> 44 days
+/// 95400 -> 1 day 2 hours 30 minutes
+/// ...
+pub fn print_duration(seconds: f64) -> String {
+    if !seconds.is_finite() {
+        return format!("{} seconds", seconds);
+    }
+    let sign = if seconds < 0.0 { "-" } else { "" };
+    // Round to the millisecond so that sums of fractions print cleanly.
+    let mut rest = (seconds.abs() * 1000.0).round() / 1000.0;
+    let mut parts = Vec::<String>::new();
+    for (name, size) in [("day", DAY), ("hour", 3600.0), ("minute", 60.0)].iter() {
+        let count = (rest / size).floor();
+        if count > 0.0 {
+            parts.push(plural(count, name));
+            rest -= count * size;
+        }
+    }
+    if rest > 0.0 || parts.is_empty() {
+        let rest = (rest * 1000.0).round() / 1000.0;
+        parts.push(plural(rest, "second"));
+    }
+    return format!("{}{}", sign, parts.join(" "));
+}
+
+/// Function that prints a date in the given format; `DateFormat::Iso` is `print_date`. The time
+/// of day is left off when it is midnight.
+/// # Example
+/// Long: 1792315800 -> 18 October 2026 09:30
+/// Us: 1792315800 -> 10/18/2026 9:30 AM
+/// ...
+pub fn format_date(seconds: f64, format: DateFormat) -> String {
+    if format == DateFormat::Iso {
+        return print_date(seconds);
+    }
+    let seconds = seconds.round() as i64;
+    let (year, month, date) = civil_from_days(seconds.div_euclid(86400));
+    let time = seconds.rem_euclid(86400);
+    let (hour, minute, second) = (time / 3600, time % 3600 / 60, time % 60);
+    let mut ret = match format {
+        DateFormat::Us => format!("{:02}/{:02}/{:04}", month, date, year),
+        _ => format!("{} {} {:04}", date, MONTHS[month as usize - 1], year),
+    };
+    if time != 0 {
+        let clock_hour = match format {
+            DateFormat::Us if hour % 12 == 0 => String::from("12"),
+            DateFormat::Us => format!("{}", hour % 12),
+            _ => format!("{:02}", hour),
+        };
+        ret.push_str(&format!(" {}:{:02}", clock_hour, minute));
+        if second != 0 {
+            ret.push_str(&format!(":{:02}", second));
+        }
+        if format == DateFormat::Us {
+            ret.push_str(if hour < 12 { " AM" } else { " PM" });
+        }
+    }
+    return ret;
+}
+
+/// Function that prints a duration in the given format; `DurationFormat::Words` is
+/// `print_duration`.
+/// # Example
+/// Short: 95400 -> 1d 2h 30m
+/// Clock: 95400 -> 26:30:00
+/// ...
+pub fn format_duration(seconds: f64, format: DurationFormat) -> String {
+    if format == DurationFormat::Words || !seconds.is_finite() {
+        return print_duration(seconds);
+    }
+    let sign = if seconds < 0.0 { "-" } else { "" };
+    let rest = (seconds.abs() * 1000.0).round() / 1000.0;
+    if format == DurationFormat::Clock {
+        let hours = (rest / 3600.0).floor();
+        let minutes = ((rest - hours * 3600.0) / 60.0).floor();
+        let rest = ((rest - hours * 3600.0 - minutes * 60.0) * 1000.0).round() / 1000.0;
+        let mut second = format!("{:06.3}", rest);
+        second = String::from(second.trim_end_matches('0').trim_end_matches('.'));
+        return format!("{}{}:{:02}:{}", sign, hours, minutes, second);
+    }
+    let mut rest = rest;
+    let mut parts = Vec::<String>::new();
+    for (name, size) in [("d", DAY), ("h", 3600.0), ("m", 60.0)].iter() {
+        let count = (rest / size).floor();
+        if count > 0.0 {
+            parts.push(format!("{}{}", count, name));
+            rest -= count * size;
+        }
+    }
+    if rest > 0.0 || parts.is_empty() {
+        parts.push(format!("{}s", (rest * 1000.0).round() / 1000.0));
+    }
+    return format!("{}{}", sign, parts.join(" "));
+}
+
+fn plural(count: f64, name: &str) -> String {
+    if count == 1.0 {
+        return format!("{} {}", count, name);
+    }
+    return format!("{} {}s", count, name);
+}
+
+/// Function that returns the length in seconds of a unit of time, e.g. days, or None when the
+/// name is not a unit. Months and years are left out because their length varies.
+pub fn unit_seconds(name: &str) -> Option<f64> {
+    match name {
+        "second" | "seconds" => return Some(1.0),
+        "minute" | "minutes" => return Some(60.0),
+        "hour" | "hours" => return Some(3600.0),
+        "day" | "days" => return Some(DAY),
+        "week" | "weeks" => return Some(7.0 * DAY),
+        _ => return None,
+    }
+}
+
+/// Private function that splits text on a separator into numbers with exactly the given
+/// numbers of digits.
+fn split_digits(text: &str, separator: char, widths: &[usize]) -> Option<Vec<i64>> {
+    let parts = text.split(separator).collect::<Vec<&str>>();
+    if parts.len() != widths.len() {
+        return None;
+    }
+    let mut ret = Vec::<i64>::new();
+    for (part, width) in parts.iter().zip(widths.iter()) {
+        if part.len() != *width || !part.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        ret.push(part.parse::<i64>().ok()?);
+    }
+    return Some(ret);
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => return 29,
+        2 => return 28,
+        4 | 6 | 9 | 11 => return 30,
+        _ => return 31,
+    }
+}
+
+/// Private function that counts the days from 1970-01-01 to a date of the proleptic Gregorian
+/// calendar, following Howard Hinnant's days_from_civil.
+fn days_from_civil(year: i64, month: i64, date: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + date - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    return era * 146097 + day_of_era - 719468;
+}
+
+/// Private function that turns a count of days from 1970-01-01 back into year, month and day.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let date = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    return (year, month, date);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(Some(0.0), parse_date("1970-01-01"));
+        assert_eq!(Some(1792281600.0), parse_date("2026-10-18"));
+        assert_eq!(Some(1792315800.0), parse_date("2026-10-18T09:30"));
+        assert_eq!(Some(1792315845.0), parse_date("2026-10-18T09:30:45"));
+        assert_eq!(Some(951782400.0), parse_date("2000-02-29"));
+        assert_eq!(None, parse_date("2026-02-29"));
+        assert_eq!(None, parse_date("2026-13-01"));
+        assert_eq!(None, parse_date("2026-10-18T24:00"));
+        assert_eq!(None, parse_date("26-10-18"));
+        assert!(is_date_literal("2026-02-30"));
+        assert!(!is_date_literal("days"));
+    }
+
+    #[test]
+    fn test_print_date() {
+        assert_eq!("2026-10-18", print_date(1792281600.0));
+        assert_eq!("2026-10-18T09:30", print_date(1792315800.0));
+        assert_eq!("2026-10-18T09:30:45", print_date(1792315845.0));
+        assert_eq!("1969-12-31", print_date(-DAY));
+        assert_eq!("2000-02-29", print_date(951782400.0));
+    }
+
+    #[test]
+    fn test_print_duration() {
+        assert_eq!("44 days", print_duration(44.0 * DAY));
+        assert_eq!("1 day 2 hours 30 minutes", print_duration(95400.0));
+        assert_eq!("-1.5 seconds", print_duration(-1.5));
+        assert_eq!("0 seconds", print_duration(0.0));
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!("2026-10-18T09:30", format_date(1792315800.0, DateFormat::Iso));
+        assert_eq!("18 October 2026", format_date(1792281600.0, DateFormat::Long));
+        assert_eq!("18 October 2026 09:30:45", format_date(1792315845.0, DateFormat::Long));
+        assert_eq!("10/18/2026 9:30 AM", format_date(1792315800.0, DateFormat::Us));
+        assert_eq!("10/18/2026 12:05 PM", format_date(1792281600.0 + 43500.0, DateFormat::Us));
+        assert_eq!("12/31/1969 12:30 AM", format_date(-DAY + 1800.0, DateFormat::Us));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!("1 day 2 hours 30 minutes", format_duration(95400.0, DurationFormat::Words));
+        assert_eq!("1d 2h 30m", format_duration(95400.0, DurationFormat::Short));
+        assert_eq!("-1.5s", format_duration(-1.5, DurationFormat::Short));
+        assert_eq!("0s", format_duration(0.0, DurationFormat::Short));
+        assert_eq!("26:30:00", format_duration(95400.0, DurationFormat::Clock));
+        assert_eq!("-0:01:05.25", format_duration(-65.25, DurationFormat::Clock));
+        assert_eq!("0:00:00", format_duration(0.0, DurationFormat::Clock));
+    }
+}
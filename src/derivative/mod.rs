@@ -0,0 +1,227 @@
+#![allow(clippy::needless_return)]
+
+use expression::Expression;
+use simplify;
+use std::collections::HashMap;
+
+/// Function that differentiates an equation tree with respect to a variable, covering the
+/// arithmetic operators, powers, the chain rule and the built-in elementary functions.
+/// The result is not simplified; see `differentiate_simplified`.
+/// # Example
+/// x^3 + 2 * x -> 3 * x^(3 - 1) * 1 + (0 * x + 2 * 1)
+/// ...
+pub fn differentiate(expression: &Expression, variable: &str) -> Result<Expression, String> {
+    match *expression {
+        Expression::Value(_) => {
+            return Ok(Expression::Value(0.0));
+        }
+        Expression::Variable(ref name) => {
+            return Ok(Expression::Value(if name == variable { 1.0 } else { 0.0 }));
+        }
+        Expression::Operator(ref op, ref operands) => match op.as_str() {
+            "+" | "-" => {
+                let mut derivatives = Vec::<Expression>::new();
+                for operand in operands.iter() {
+                    derivatives.push(differentiate(operand, variable)?);
+                }
+                return Ok(Expression::Operator(op.clone(), derivatives));
+            }
+            "*" => {
+                // Product rule: one term per factor, differentiating that factor only.
+                let mut terms = Vec::<Expression>::new();
+                for index in 0..operands.len() {
+                    let mut factors = operands.clone();
+                    factors[index] = differentiate(&operands[index], variable)?;
+                    terms.push(Expression::Operator(String::from("*"), factors));
+                }
+                if terms.len() == 1 {
+                    return Ok(terms.pop().unwrap());
+                }
+                return Ok(Expression::Operator(String::from("+"), terms));
+            }
+            "/" if operands.len() == 1 => {
+                let denominator = &operands[0];
+                let numerator = negate(differentiate(denominator, variable)?);
+                return Ok(binary("/", numerator, binary("^", denominator.clone(), Expression::Value(2.0))));
+            }
+            "/" if operands.len() == 2 => {
+                // Quotient rule: (f'g - fg') / g^2
+                let (f, g) = (&operands[0], &operands[1]);
+                let numerator = binary(
+                    "-",
+                    binary("*", differentiate(f, variable)?, g.clone()),
+                    binary("*", f.clone(), differentiate(g, variable)?),
+                );
+                return Ok(binary("/", numerator, binary("^", g.clone(), Expression::Value(2.0))));
+            }
+            "^" => {
+                let (f, g) = (&operands[0], &operands[1]);
+                if !depends_on(g, variable) {
+                    // Power rule with the chain rule: g * f^(g - 1) * f'
+                    let lowered = binary("^", f.clone(), binary("-", g.clone(), Expression::Value(1.0)));
+                    return Ok(product(vec![g.clone(), lowered, differentiate(f, variable)?]));
+                }
+                // General case: f^g * (g' * ln(f) + g * f' / f)
+                let logarithm = function("ln", f.clone());
+                let inner = binary(
+                    "+",
+                    binary("*", differentiate(g, variable)?, logarithm),
+                    binary("/", binary("*", g.clone(), differentiate(f, variable)?), f.clone()),
+                );
+                return Ok(binary("*", expression.clone(), inner));
+            }
+            _ => {
+                return Err(format!("Cannot differentiate operator {}.", op));
+            }
+        },
+        Expression::Function(ref name, ref args) => {
+            if name == "diff" {
+                return differentiate(&differentiate_call(args)?, variable);
+            }
+            if args.len() != 1 {
+                return Err(format!("Cannot differentiate function {}.", name));
+            }
+            let u = &args[0];
+            let outer = match name.as_str() {
+                "sin" => function("cos", u.clone()),
+                "cos" => negate(function("sin", u.clone())),
+                "tan" => binary("/", Expression::Value(1.0), binary("^", function("cos", u.clone()), Expression::Value(2.0))),
+                "exp" => function("exp", u.clone()),
+                "ln" => binary("/", Expression::Value(1.0), u.clone()),
+                "log" => binary("/", Expression::Value(1.0), binary("*", u.clone(), function("ln", Expression::Value(10.0)))),
+                "sqrt" => binary("/", Expression::Value(1.0), binary("*", Expression::Value(2.0), function("sqrt", u.clone()))),
+                "abs" => binary("/", u.clone(), function("abs", u.clone())),
+                "asin" => binary("/", Expression::Value(1.0), function("sqrt", one_minus_square(u))),
+                "acos" => negate(binary("/", Expression::Value(1.0), function("sqrt", one_minus_square(u)))),
+                "atan" => binary("/", Expression::Value(1.0), binary("+", Expression::Value(1.0), binary("^", u.clone(), Expression::Value(2.0)))),
+                _ => return Err(format!("Cannot differentiate function {}.", name)),
+            };
+            // Chain rule.
+            return Ok(binary("*", outer, differentiate(u, variable)?));
+        }
+    }
+}
+
+/// Function that differentiates an equation tree and simplifies the result, treating every
+/// variable except the one being differentiated by as known when it has a value.
+pub fn differentiate_simplified(expression: &Expression, variable: &str, map: &HashMap<String, f64>) -> Result<Expression, String> {
+    let mut known = map.clone();
+    known.remove(variable);
+    let derivative = differentiate(&simplify::simplify(expression, &known), variable)?;
+    return Ok(simplify::simplify(&derivative, &known));
+}
+
+/// Function that evaluates a diff(equation, variable) call, e.g. diff(x^2, x) -> 2 * x^(2 - 1) * 1.
+pub fn differentiate_call(args: &[Expression]) -> Result<Expression, String> {
+    let (expression, variable) = get_call_arguments(args)?;
+    return differentiate(expression, variable);
+}
+
+/// Function that splits the arguments of a diff(equation, variable) call.
+pub fn get_call_arguments(args: &[Expression]) -> Result<(&Expression, &str), String> {
+    match args {
+        [ref expression, Expression::Variable(ref variable)] => {
+            return Ok((expression, variable));
+        }
+        _ => {
+            return Err(String::from("diff takes an equation and a variable, e.g. diff(x^2, x)."));
+        }
+    }
+}
+
+/// Function that reports whether an equation tree mentions a variable.
+pub fn depends_on(expression: &Expression, variable: &str) -> bool {
+    match *expression {
+        Expression::Value(_) => return false,
+        Expression::Variable(ref name) => return name == variable,
+        Expression::Operator(_, ref operands) | Expression::Function(_, ref operands) => {
+            return operands.iter().any(|operand| depends_on(operand, variable));
+        }
+    }
+}
+
+fn one_minus_square(u: &Expression) -> Expression {
+    return binary("-", Expression::Value(1.0), binary("^", u.clone(), Expression::Value(2.0)));
+}
+
+fn negate(expression: Expression) -> Expression {
+    return Expression::Operator(String::from("-"), vec![expression]);
+}
+
+fn function(name: &str, arg: Expression) -> Expression {
+    return Expression::Function(String::from(name), vec![arg]);
+}
+
+fn product(factors: Vec<Expression>) -> Expression {
+    return Expression::Operator(String::from("*"), factors);
+}
+
+fn binary(op: &str, left: Expression, right: Expression) -> Expression {
+    return Expression::Operator(String::from(op), vec![left, right]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expression;
+    use infix;
+    use prefix;
+
+    fn diff(input: &str, variable: &str) -> String {
+        let prefix_notation = prefix::translate_infix(input).unwrap();
+        let expression = expression::from_prefix(&prefix_notation).unwrap();
+        let derivative = differentiate_simplified(&expression, variable, &HashMap::<String, f64>::new());
+        return infix::print_infix(&derivative.unwrap());
+    }
+
+    #[test]
+    fn test_polynomial() {
+        assert_eq!("3 * x^2 + 2", diff("x^3 + 2*x", "x"));
+        assert_eq!("0", diff("y^2 + 4", "x"));
+        assert_eq!("2 * x * y", diff("x^2 * y", "x"));
+    }
+
+    #[test]
+    fn test_quotient() {
+        assert_eq!("-1 / x^2", diff("1 / x", "x"));
+        assert_eq!("0.5", diff("x / 2", "x"));
+        assert_eq!("1 / y", diff("x / y", "x"));
+    }
+
+    #[test]
+    fn test_chain_rule() {
+        assert_eq!("2 * cos(2 * x)", diff("sin(2x)", "x"));
+        assert_eq!("2 * exp(x^2) * x", diff("exp(x^2)", "x"));
+        assert_eq!("-sin(x)", diff("cos(x)", "x"));
+        assert_eq!("1 / x", diff("ln(x)", "x"));
+        assert_eq!("e^x", diff("e^x", "x"));
+    }
+
+    #[test]
+    fn test_variable_exponent() {
+        assert_eq!("0.6931471805599453 * 2^x", diff("2^x", "x"));
+    }
+
+    #[test]
+    fn test_nested_diff() {
+        assert_eq!("6 * x", diff("diff(x^3, x)", "x"));
+    }
+
+    #[test]
+    fn test_known_variables() {
+        let mut map = HashMap::<String, f64>::new();
+        map.insert(String::from("a"), 3.0);
+        map.insert(String::from("x"), 10.0);
+        let expression = expression::from_prefix(&prefix::translate_infix("a * x^2").unwrap()).unwrap();
+        let derivative = differentiate_simplified(&expression, "x", &map).unwrap();
+        assert_eq!("6 * x", infix::print_infix(&derivative));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let expression = expression::from_prefix("( max x 2 )").unwrap();
+        assert_eq!(Err(String::from("Cannot differentiate function max.")), differentiate(&expression, "x"));
+        let expression = expression::from_prefix("( diff x 2 )").unwrap();
+        assert!(differentiate(&expression, "x").is_err());
+    }
+}
@@ -0,0 +1,168 @@
+#![allow(clippy::needless_return)]
+
+use calculator;
+use calculator::Cell;
+
+/// Enum that defines a node of an equation tree:
+/// * A Value [Any numeric. e.g. 1, 2, 3.141414...]
+/// * A Variable [Any non-numeric and not an operator. Including whole words.]
+/// * An Operator [+, -, *, /, ^, =, ==, <, and, ...] together with its operands, in order.
+/// * A Function [A built-in function, e.g. max, sin] together with its arguments, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Value(f64),
+    Variable(String),
+    Operator(String, Vec<Expression>),
+    Function(String, Vec<Expression>),
+}
+
+/// Function that builds an equation tree from a vector of cells in prefix order.
+pub fn from_cells(vec: &[Cell]) -> Result<Expression, String> {
+    let mut iter = vec.iter();
+    match build(&mut iter)? {
+        Some(expression) => {
+            if iter.next().is_some() {
+                return Err(String::from("Did not use all values!"));
+            }
+            return Ok(expression);
+        }
+        None => {
+            return Err(String::from("Nothing to translate."));
+        }
+    }
+}
+
+/// Function that builds an equation tree from a prefix notated equation.
+/// '=' is treated as an ordinary binary operator.
+pub fn from_prefix(prefix_notation: &str) -> Result<Expression, String> {
+    let vec = calculator::parse_to_vec(prefix_notation)?;
+    return from_cells(&vec);
+}
+
+/// Function that flattens an equation tree back into a vector of cells in prefix order.
+pub fn to_cells(expression: &Expression) -> Vec<Cell> {
+    let mut ret = Vec::<Cell>::new();
+    flatten(expression, &mut ret);
+    return ret;
+}
+
+/// Private function that consumes one complete prefix sub-equation from the iterator.
+fn build<'a, I>(iter: &mut I) -> Result<Option<Expression>, String>
+where
+    I: Iterator<Item = &'a Cell>,
+{
+    match iter.next() {
+        Some(Cell::Operator(ref op)) => {
+            let mut operands = Vec::<Expression>::new();
+            while operands.len() < 2 {
+                match build(iter)? {
+                    Some(operand) => operands.push(operand),
+                    None => {
+                        return Err(format!("Not enough values to apply to Operator {}.", op));
+                    }
+                }
+            }
+            return Ok(Some(Expression::Operator(op.clone(), operands)));
+        }
+        Some(Cell::Function(ref name, ref count)) => {
+            let mut args = Vec::<Expression>::new();
+            while args.len() < *count {
+                match build(iter)? {
+                    Some(arg) => args.push(arg),
+                    None => {
+                        return Err(format!("Not enough values to apply to Function {}.", name));
+                    }
+                }
+            }
+            if calculator::is_function(name) {
+                return Ok(Some(Expression::Function(name.clone(), args)));
+            }
+            return Ok(Some(Expression::Operator(name.clone(), args)));
+        }
+        Some(Cell::Variable(ref val)) => {
+            return Ok(Some(Expression::Variable(val.clone())));
+        }
+        Some(Cell::Value(ref val)) => {
+            return Ok(Some(Expression::Value(*val)));
+        }
+        None => {
+            return Ok(None);
+        }
+    }
+}
+
+fn flatten(expression: &Expression, ret: &mut Vec<Cell>) {
+    match *expression {
+        Expression::Value(ref val) => ret.push(Cell::Value(*val)),
+        Expression::Variable(ref val) => ret.push(Cell::Variable(val.clone())),
+        Expression::Operator(ref op, ref operands) => {
+            if operands.len() == 2 {
+                ret.push(Cell::Operator(op.clone()));
+            } else {
+                ret.push(Cell::Function(op.clone(), operands.len()));
+            }
+            for operand in operands.iter() {
+                flatten(operand, ret);
+            }
+        }
+        Expression::Function(ref name, ref args) => {
+            ret.push(Cell::Function(name.clone(), args.len()));
+            for arg in args.iter() {
+                flatten(arg, ret);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Expression {
+        return Expression::Variable(String::from(name));
+    }
+
+    fn op(name: &str, left: Expression, right: Expression) -> Expression {
+        return Expression::Operator(String::from(name), vec![left, right]);
+    }
+
+    #[test]
+    fn test_from_prefix() {
+        let result = op("*", op("+", var("a"), var("b")), Expression::Value(3.0));
+        assert_eq!(Ok(result), from_prefix("* + a b 3"));
+    }
+
+    #[test]
+    fn test_from_prefix_variadic() {
+        let values = vec![Expression::Value(1.0), Expression::Value(2.0), Expression::Value(3.0)];
+        let result = Expression::Operator(String::from("+"), values);
+        assert_eq!(Ok(result), from_prefix("(+ 1 2 3)"));
+        let result = Expression::Function(String::from("max"), vec![var("a"), var("b"), var("c")]);
+        assert_eq!(Ok(result.clone()), from_prefix("(max a b c)"));
+        assert_eq!(Ok(result.clone()), from_cells(&to_cells(&result)));
+    }
+
+    #[test]
+    fn test_from_prefix_ignores_parentheses() {
+        let result = op("/", op("+", var("a"), var("b")), op("-", var("c"), var("d")));
+        assert_eq!(Ok(result), from_prefix("/ ( + a b ) ( - c d )"));
+    }
+
+    #[test]
+    fn test_from_prefix_missing_operand() {
+        let result = Err(String::from("Not enough values to apply to Operator -."));
+        assert_eq!(result, from_prefix("+ a - b"));
+    }
+
+    #[test]
+    fn test_from_prefix_extra_operand() {
+        let result = Err(String::from("Did not use all values!"));
+        assert_eq!(result, from_prefix("+ a b c"));
+    }
+
+    #[test]
+    fn test_round_trip_cells() {
+        let expression = from_prefix("+ a * b / c -4").unwrap();
+        assert_eq!(Ok(expression.clone()), from_cells(&to_cells(&expression)));
+    }
+}
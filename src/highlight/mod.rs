@@ -0,0 +1,138 @@
+#![allow(clippy::needless_return)]
+
+use calculator;
+use token;
+use token::{CalcError, TokenKind};
+
+/// Enum that defines how a piece of an infix equation is shown:
+/// * Number [A number or a date, e.g. 2.5 or 2026-10-18]
+/// * Variable [A name that is not a function, e.g. x, pi or days]
+/// * Operator [An operator, a comma or a word operator such as and]
+/// * Function [The name of a built-in function, e.g. sin]
+/// * Paren [A bracket]
+/// * Error [Text the equation cannot be read with, or the span of a `CalcError`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Class {
+    Number,
+    Variable,
+    Operator,
+    Function,
+    Paren,
+    Error,
+}
+
+impl Class {
+    /// Function that returns the name of the class, as used in class names of the page.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Class::Number => return "number",
+            Class::Variable => return "variable",
+            Class::Operator => return "operator",
+            Class::Function => return "function",
+            Class::Paren => return "paren",
+            Class::Error => return "error",
+        }
+    }
+}
+
+/// Struct that holds one highlighted piece of an equation. `start` and `end` count characters
+/// as for `token::Token`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub class: Class,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Function that splits an infix equation into highlighted pieces, in order. Characters that
+/// cannot be read are marked as errors and reading carries on after them, and the pieces that
+/// overlap the span of `error` are marked as errors too. An error at the end of the equation
+/// gives an empty piece there. Whitespace is left out.
+/// # Example
+/// sin(x) + 2 -> function, paren, variable, paren, operator, number
+/// ...
+pub fn highlight(input: &str, error: Option<&CalcError>) -> Vec<Span> {
+    let mut ret = Vec::<Span>::new();
+    let mut offset = 0;
+    let mut rest = input;
+    loop {
+        let (tokens, failure) = match token::tokenize(rest) {
+            Ok(tokens) => (tokens, None),
+            Err(e) => {
+                // Everything before the failure reads the same on its own.
+                let before = rest.chars().take(e.start).collect::<String>();
+                (token::tokenize(&before).unwrap_or_default(), Some(e))
+            }
+        };
+        for token in tokens.iter() {
+            ret.push(Span { class: classify(token), start: token.start + offset, end: token.end + offset });
+        }
+        match failure {
+            Some(e) => {
+                let end = e.end.max(e.start + 1);
+                ret.push(Span { class: Class::Error, start: e.start + offset, end: end + offset });
+                rest = &rest[rest.char_indices().nth(end).map_or(rest.len(), |(index, _)| index)..];
+                offset += end;
+            }
+            None => break,
+        }
+    }
+    if let Some(error) = error {
+        let mut marked = false;
+        for span in ret.iter_mut() {
+            if span.start < error.end && error.start < span.end {
+                span.class = Class::Error;
+                marked = true;
+            }
+        }
+        if !marked {
+            let position = ret.iter().position(|span| span.start >= error.start).unwrap_or(ret.len());
+            ret.insert(position, Span { class: Class::Error, start: error.start, end: error.start });
+        }
+    }
+    return ret;
+}
+
+/// Private function that finds the class of a token.
+fn classify(token: &token::Token) -> Class {
+    match token.kind {
+        TokenKind::Number | TokenKind::Date => return Class::Number,
+        TokenKind::Operator | TokenKind::Comma => return Class::Operator,
+        TokenKind::Open | TokenKind::Close => return Class::Paren,
+        TokenKind::Identifier if calculator::is_operator(&token.text) || token.text == "not" => return Class::Operator,
+        TokenKind::Identifier if calculator::is_function(&token.text) => return Class::Function,
+        TokenKind::Identifier => return Class::Variable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes(input: &str, error: Option<&CalcError>) -> Vec<(&'static str, usize, usize)> {
+        return highlight(input, error).iter().map(|span| (span.class.name(), span.start, span.end)).collect();
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(
+            vec![("function", 0, 3), ("paren", 3, 4), ("variable", 4, 5), ("paren", 5, 6), ("operator", 7, 8), ("number", 9, 10)],
+            classes("sin(x) + 2", None)
+        );
+        assert_eq!(vec![("variable", 0, 1), ("operator", 2, 5), ("operator", 6, 9), ("variable", 10, 11)], classes("a and not b", None));
+    }
+
+    #[test]
+    fn test_highlight_unreadable() {
+        assert_eq!(vec![("variable", 0, 1), ("error", 2, 3), ("variable", 4, 5), ("error", 5, 6), ("number", 6, 7)], classes("a # b#1", None));
+        assert_eq!(vec![("number", 0, 1), ("error", 2, 3), ("number", 4, 5)], classes("1 ! 2", None));
+    }
+
+    #[test]
+    fn test_highlight_error_span() {
+        let error = CalcError::new(String::from("Unexpected '*' at position 5."), 4, 5);
+        assert_eq!(vec![("variable", 0, 1), ("operator", 2, 3), ("error", 4, 5), ("variable", 6, 7)], classes("a + * b", Some(&error)));
+        let error = CalcError::new(String::from("Unexpected end of equation."), 3, 3);
+        assert_eq!(vec![("variable", 0, 1), ("operator", 2, 3), ("error", 3, 3)], classes("a + ", Some(&error)));
+    }
+}
@@ -1,139 +1,294 @@
-use unicode_segmentation::UnicodeSegmentation;
-use regex::Regex;
+#![allow(clippy::needless_return)]
+
+use expression;
+use expression::Expression;
 
 /// Function that translates a prefix notated equation to an infix notated equation.
 /// # Example
 /// + a b -> a + b
+/// / + a b c -> (a + b) / c
 /// ...
-pub fn translate_prefix(prefix_notation: &str) -> String {
-    let whitespace = Regex::new(r"^\s+?").unwrap();
-    let mut ret = String::new();
-    let mut operators = Vec::new();
-    let mut word_or_punctuation = prefix_notation
-        .split_word_bounds()
-        .filter(|x| !whitespace.is_match(x))
-        .peekable();
-
-    loop {
-        let item = word_or_punctuation.next();
-        match item {
-            Some("(") => {
-                ret.push_str(item.unwrap());
-            }
-            Some(")") => {
-                ret.push_str(item.unwrap());
-                match word_or_punctuation.peek() {
-                    Some(&")") => {
-                        // Do nothing.
-                    }
-                    _ => if !operators.is_empty() {
-                        ret.push_str(" ");
-                        ret.push_str(operators.pop().unwrap());
-                        ret.push_str(" ");
-                    },
-                }
+#[allow(clippy::doc_lazy_continuation)]
+pub fn translate_prefix(prefix_notation: &str) -> Result<String, String> {
+    let expression = expression::from_prefix(prefix_notation)?;
+    return Ok(print_infix(&expression));
+}
+
+/// Function that prints an equation tree in infix notation, emitting only the parentheses
+/// that operator precedence and associativity require.
+/// # Example
+/// / (+ a b) c -> (a + b) / c
+/// - a (- b c) -> a - (b - c)
+/// ...
+#[allow(clippy::doc_lazy_continuation)]
+pub fn print_infix(expression: &Expression) -> String {
+    match *expression {
+        Expression::Value(ref val) => {
+            return format!("{}", val);
+        }
+        Expression::Variable(ref val) => {
+            return val.clone();
+        }
+        Expression::Operator(ref op, ref operands) if operands.len() == 1 => {
+            let operand = match operands[0] {
+                Expression::Operator(_, _) => format!("({})", print_infix(&operands[0])),
+                Expression::Value(val) if val < 0.0 => format!("({})", val),
+                _ => print_infix(&operands[0]),
+            };
+            if op == "/" {
+                return format!("1 / {}", operand);
             }
-            Some("=") | Some("+") | Some("-") | Some("*") | Some("/") => {
-                operators.push(item.unwrap());
+            return format!("{}{}", op, operand);
+        }
+        Expression::Function(ref name, ref args) => {
+            let args = args.iter().map(print_infix).collect::<Vec<String>>();
+            if name == "list" {
+                return format!("[{}]", args.join(", "));
             }
-            Some(_) => {
-                ret.push_str(item.unwrap());
-                if operators.is_empty() {
-                    // do nothing
-
-                } else {
-                    match word_or_punctuation.peek() {
-                        Some(&")") => {
-                            // do nothing
+            return format!("{}({})", name, args.join(", "));
+        }
+        Expression::Operator(ref op, ref operands) => {
+            let precedence = get_precedence(op);
+            let mut ret = String::new();
+            for (index, operand) in operands.iter().enumerate() {
+                if index > 0 && op == "^" {
+                    ret.push_str(op);
+                } else if index > 0 {
+                    ret.push(' ');
+                    ret.push_str(op);
+                    ret.push(' ');
+                }
+                let needs_parentheses = match *operand {
+                    Expression::Operator(ref inner, _) => {
+                        let inner_precedence = get_precedence(inner);
+                        let leading = index == 0;
+                        if leading == is_right_associative(op) {
+                            inner_precedence <= precedence
+                        } else {
+                            inner_precedence < precedence
                         }
-                        _ => if !operators.is_empty() {
-                            ret.push_str(" ");
-                            ret.push_str(operators.pop().unwrap());
-                            ret.push_str(" ");
-                        },
                     }
+                    // -2^2 reads as -(2^2), so a negative base needs parentheses.
+                    Expression::Value(val) => op == "^" && index == 0 && val < 0.0,
+                    _ => false,
+                };
+                if needs_parentheses {
+                    ret.push('(');
+                    ret.push_str(&print_infix(operand));
+                    ret.push(')');
+                } else {
+                    ret.push_str(&print_infix(operand));
                 }
             }
-            None => break,
+            return ret;
         }
     }
+}
 
-    return ret;
+/// Private function that returns the binding strength of an infix operator. Must agree with the
+/// infix parser in the prefix module.
+fn get_precedence(op: &str) -> i32 {
+    match op {
+        "^" => 10,
+        "*" | "/" => 8,
+        "+" | "-" => 7,
+        "in" => 6,
+        "==" | "!=" | "<" | "<=" | ">" | ">=" => 5,
+        "and" => 4,
+        "or" => 3,
+        "=" => 1,
+        _ => 0,
+    }
+}
+
+fn is_right_associative(op: &str) -> bool {
+    return op == "=" || op == "^";
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use calculator;
+    use postfix;
+    use prefix;
+    use std::collections::HashMap;
 
     #[test]
     fn test_simple_add() {
         let input = "+ 2 2";
         let result = "2 + 2";
-        assert_eq!(result, translate_prefix(input));
+        assert_eq!(result, translate_prefix(input).unwrap());
     }
 
     #[test]
     fn test_simple_subtract() {
         let input = "- 2 2";
         let result = "2 - 2";
-        assert_eq!(result, translate_prefix(input));
+        assert_eq!(result, translate_prefix(input).unwrap());
     }
 
     #[test]
     fn test_simple_multiply() {
         let input = "* 2 2";
         let result = "2 * 2";
-        assert_eq!(result, translate_prefix(input));
+        assert_eq!(result, translate_prefix(input).unwrap());
     }
 
     #[test]
     fn test_simple_divide() {
         let input = "/ 2 2";
         let result = "2 / 2";
-        assert_eq!(result, translate_prefix(input));
+        assert_eq!(result, translate_prefix(input).unwrap());
     }
 
     #[test]
     fn test_complex_one() {
         let result = "2 + pi / 35";
         let input = "+ 2 / pi 35";
-        assert_eq!(result, translate_prefix(input));
+        assert_eq!(result, translate_prefix(input).unwrap());
     }
 
     #[test]
     fn test_complex_two() {
-        let result = "a + b * c / d";
+        let result = "a + b * (c / d)";
         let input = "+ a * b / c d";
-        assert_eq!(result, translate_prefix(input));
+        assert_eq!(result, translate_prefix(input).unwrap());
     }
     #[test]
     fn test_complex_three() {
         let result = "(a + b * c) / (d - f / g)";
         let input = "/ ( + a * b c ) ( - d / f g )";
-        assert_eq!(result, translate_prefix(input));
+        assert_eq!(result, translate_prefix(input).unwrap());
     }
     #[test]
     fn test_complex_four() {
-        let result = "(a + b * c / (d - f / (g * h / i)))";
+        let result = "a + b * (c / (d - f / (g * (h / i))))";
         let input = "( + a * b / c ( - d / f ( * g / h i ) ) )";
-        assert_eq!(result, translate_prefix(input));
+        assert_eq!(result, translate_prefix(input).unwrap());
     }
     #[test]
     fn test_complex_five() {
-        let result = "(j + k) * (a + b * c / (d - f / (g * h / i)))";
+        let result = "(j + k) * (a + b * (c / (d - f / (g * (h / i)))))";
         let input = "* ( + j k ) ( + a * b / c ( - d / f ( * g / h i ) ) )";
-        assert_eq!(result, translate_prefix(input));
+        assert_eq!(result, translate_prefix(input).unwrap());
     }
     #[test]
     fn test_complex_six() {
-        let result = "(a + b * c / (d - f / (g * h / i))) + (j + k)";
+        let result = "a + b * (c / (d - f / (g * (h / i)))) + (j + k)";
         let input = "+ ( + a * b / c ( - d / f ( * g / h i ) ) ) ( + j k )";
-        assert_eq!(result, translate_prefix(input));
+        assert_eq!(result, translate_prefix(input).unwrap());
     }
     #[test]
     fn test_infix_equals() {
         let result = "a = 3";
         let input = "= a 3";
-        assert_eq!(result, translate_prefix(input));
+        assert_eq!(result, translate_prefix(input).unwrap());
+    }
+    #[test]
+    fn test_precedence_parentheses() {
+        let result = "(a + b) * c";
+        let input = "* + a b c";
+        assert_eq!(result, translate_prefix(input).unwrap());
+    }
+    #[test]
+    fn test_associativity_parentheses() {
+        assert_eq!("a - b - c", translate_prefix("- - a b c").unwrap());
+        assert_eq!("a - (b - c)", translate_prefix("- a - b c").unwrap());
+        assert_eq!("a = b = 3", translate_prefix("= a = b 3").unwrap());
+    }
+    #[test]
+    fn test_negative_value() {
+        let result = "-3 - -4";
+        let input = "- -3 -4";
+        assert_eq!(result, translate_prefix(input).unwrap());
+    }
+    #[test]
+    fn test_variadic() {
+        assert_eq!("1 + 2 + 3 + 4", translate_prefix("(+ 1 2 3 4)").unwrap());
+        assert_eq!("a - (b - c) - d", translate_prefix("(- a (- b c) d)").unwrap());
+        assert_eq!("-(a + b)", translate_prefix("(- (+ a b))").unwrap());
+        assert_eq!("max(a, b * c, 3)", translate_prefix("(max a * b c 3)").unwrap());
+        assert_eq!("sin(x) / 2", translate_prefix("/ sin x 2").unwrap());
+    }
+    #[test]
+    fn test_lists() {
+        assert_eq!("[x + 1, y]", translate_prefix("( list + x 1 y )").unwrap());
+    }
+
+    #[test]
+    fn test_comparisons_and_logic() {
+        assert_eq!("x + 1 < 2 * y", translate_prefix("< + x 1 * 2 y").unwrap());
+        assert_eq!("a < b and b < c or d", translate_prefix("or and < a b < b c d").unwrap());
+        assert_eq!("a and (b or c)", translate_prefix("and a or b c").unwrap());
+        assert_eq!("a == (b == c)", translate_prefix("== a == b c").unwrap());
+        assert_eq!("if(x < 0, -x, not(p))", translate_prefix("( if < x 0 -x ( not p ) )").unwrap());
+    }
+
+    #[test]
+    fn test_missing_operand() {
+        let input = "* + a b";
+        assert!(translate_prefix(input).is_err());
+    }
+
+    /// Minimal linear congruential generator so the round trip property can be checked
+    /// against many equations without pulling in a dependency.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, bound: u64) -> u64 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            return (self.0 >> 33) % bound;
+        }
+    }
+
+    fn random_expression(rng: &mut Lcg, depth: u32) -> Expression {
+        if depth == 0 || rng.next(4) == 0 {
+            match rng.next(3) {
+                0 => return Expression::Variable(String::from(["a", "b", "c"][rng.next(3) as usize])),
+                1 => return Expression::Value(rng.next(20) as f64 / 2.0),
+                _ => return Expression::Value(-(rng.next(10) as f64) - 1.0),
+            }
+        }
+        if rng.next(5) == 0 {
+            // Functions of any number of arguments, which postfix writes with a count.
+            let name = ["mean", "median", "max", "min"][rng.next(4) as usize];
+            let args = (0..rng.next(3) + 1).map(|_| random_expression(rng, depth - 1)).collect();
+            return Expression::Function(String::from(name), args);
+        }
+        let op = ["+", "-", "*", "/"][rng.next(4) as usize];
+        let left = random_expression(rng, depth - 1);
+        let right = random_expression(rng, depth - 1);
+        return Expression::Operator(String::from(op), vec![left, right]);
+    }
+
+    fn value_of(vec: Vec<calculator::Cell>) -> f64 {
+        match vec[0] {
+            calculator::Cell::Value(val) => return val,
+            _ => panic!("Expected a value."),
+        }
+    }
+
+    #[test]
+    fn test_round_trip_property() {
+        let mut variables = HashMap::<String, f64>::new();
+        variables.insert(String::from("a"), 3.0);
+        variables.insert(String::from("b"), -7.5);
+        variables.insert(String::from("c"), 0.25);
+        let mut rng = Lcg(1974);
+        for _ in 0..200 {
+            let tree = random_expression(&mut rng, 5);
+            let postfix_notation = postfix::print_postfix(&tree);
+            assert_eq!(Ok(tree.clone()), postfix::from_postfix(&postfix_notation), "{}", postfix_notation);
+            let x = expression::to_cells(&tree);
+            let infix_notation = print_infix(&expression::from_cells(&x).unwrap());
+            let prefix_notation = prefix::translate_infix(&infix_notation).unwrap();
+            let (y, _) = calculator::parse_to_vec_and_map(&prefix_notation).unwrap();
+
+            let expected = value_of(calculator::calculate(&x, &variables).unwrap());
+            let actual = value_of(calculator::calculate(&y, &variables).unwrap());
+            if !(expected.is_nan() && actual.is_nan()) {
+                assert_eq!(expected, actual, "{} -> {}", infix_notation, prefix_notation);
+            }
+        }
     }
 }
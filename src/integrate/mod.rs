@@ -0,0 +1,278 @@
+#![allow(clippy::needless_return)]
+
+use expression::Expression;
+use std::collections::HashMap;
+use value;
+
+/// Largest number of times the equations inside one calculation may be evaluated, so that a
+/// huge sum or a badly behaved integral gives an error instead of hanging the browser.
+pub const MAX_EVALUATIONS: usize = 1_000_000;
+/// Deepest the adaptive quadrature splits an interval.
+const MAX_DEPTH: usize = 50;
+/// Error allowed in an integral, relative to the size of the answer.
+const TOLERANCE: f64 = 1e-10;
+
+/// Trait for anything that can give variables their values.
+pub trait Lookup {
+    fn lookup(&self, name: &str) -> Option<f64>;
+
+    /// Returns the named list of numbers, if there is one.
+    fn lookup_list(&self, _name: &str) -> Option<Vec<f64>> {
+        return None;
+    }
+
+    /// Returns the current time in seconds since 1970-01-01 UTC, if the host has a clock.
+    fn now(&self) -> Option<f64> {
+        return None;
+    }
+}
+
+impl Lookup for HashMap<String, f64> {
+    fn lookup(&self, name: &str) -> Option<f64> {
+        return self.get(name).cloned();
+    }
+}
+
+/// Struct that binds one variable to a value on top of another set of variables, hiding any
+/// value the variable has underneath.
+pub struct Scope<'a> {
+    parent: &'a dyn Lookup,
+    name: &'a str,
+    value: f64,
+}
+
+impl<'a> Scope<'a> {
+    pub fn new(parent: &'a dyn Lookup, name: &'a str, value: f64) -> Scope<'a> {
+        return Scope { parent, name, value };
+    }
+}
+
+impl<'a> Lookup for Scope<'a> {
+    fn lookup(&self, name: &str) -> Option<f64> {
+        if name == self.name {
+            return Some(self.value);
+        }
+        return self.parent.lookup(name);
+    }
+
+    fn lookup_list(&self, name: &str) -> Option<Vec<f64>> {
+        if name == self.name {
+            return None;
+        }
+        return self.parent.lookup_list(name);
+    }
+
+    fn now(&self) -> Option<f64> {
+        return self.parent.now();
+    }
+}
+
+/// Struct that gives variables their values from a map of numbers and a map of named lists,
+/// with the time `now` stands for.
+pub struct Variables<'a> {
+    pub numbers: &'a HashMap<String, f64>,
+    pub lists: &'a HashMap<String, Vec<f64>>,
+    pub now: Option<f64>,
+}
+
+impl<'a> Lookup for Variables<'a> {
+    fn lookup(&self, name: &str) -> Option<f64> {
+        return self.numbers.get(name).cloned();
+    }
+
+    fn lookup_list(&self, name: &str) -> Option<Vec<f64>> {
+        return self.lists.get(name).cloned();
+    }
+
+    fn now(&self) -> Option<f64> {
+        return self.now;
+    }
+}
+
+/// Function that reports whether a built-in function binds a dummy variable over its first
+/// argument, e.g. sum(k^2, k, 1, 10).
+pub fn is_binding(name: &str) -> bool {
+    match name {
+        "integrate" | "sum" | "product" => return true,
+        _ => return false,
+    }
+}
+
+/// Function that reports whether a call binds a dummy variable. sum only does so in its four
+/// argument form with a variable second, e.g. sum(k^2, k, 1, 10); otherwise it adds up a list.
+pub fn binds(name: &str, args: &[Expression]) -> bool {
+    if name == "sum" {
+        return args.len() == 4 && matches!(args[1], Expression::Variable(_));
+    }
+    return is_binding(name);
+}
+
+/// Function that evaluates an integrate, sum or product call.
+/// # Example
+/// sum(k^2, k, 1, 3) -> 14
+/// integrate(x, x, 0, 2) -> 2
+/// ...
+pub fn evaluate_binding(name: &str, args: &[Expression], variables: &dyn Lookup, budget: &mut usize) -> Result<f64, String> {
+    let variable = match args.get(1) {
+        Some(Expression::Variable(ref variable)) if args.len() == 4 => variable,
+        _ => {
+            return Err(format!("{} takes an equation, a variable and two bounds, e.g. {}(x^2, x, 0, 1).", name, name));
+        }
+    };
+    let low = value::evaluate(&args[2], variables, budget)?.number()?;
+    let high = value::evaluate(&args[3], variables, budget)?.number()?;
+    match name {
+        "integrate" => return integrate(&args[0], variable, low, high, variables, budget),
+        "sum" => return accumulate(&args[0], variable, low, high, variables, budget, 0.0, |total, val| total + val),
+        _ => return accumulate(&args[0], variable, low, high, variables, budget, 1.0, |total, val| total * val),
+    }
+}
+
+/// Function that integrates an equation over a variable from `low` to `high` by adaptive
+/// Simpson's rule.
+pub fn integrate(body: &Expression, variable: &str, low: f64, high: f64, variables: &dyn Lookup, budget: &mut usize) -> Result<f64, String> {
+    if !low.is_finite() || !high.is_finite() {
+        return Err(String::from("Bounds of integrate must be finite."));
+    }
+    let mut integrand = |x: f64| -> Result<f64, String> {
+        let val = evaluate_at(body, variable, x, variables, budget)?;
+        if !val.is_finite() {
+            return Err(format!("Cannot integrate: the equation has no finite value at {} = {}.", variable, x));
+        }
+        return Ok(val);
+    };
+    let middle = (low + high) / 2.0;
+    let (f_low, f_middle, f_high) = (integrand(low)?, integrand(middle)?, integrand(high)?);
+    let whole = simpson(low, high, f_low, f_middle, f_high);
+    return adapt(&mut integrand, low, high, f_low, f_middle, f_high, whole, TOLERANCE * whole.abs().max(1.0), 0);
+}
+
+/// Private function that refines one interval of the quadrature, splitting it in half until
+/// the halves agree with the whole. An interval that still disagrees after MAX_DEPTH splits is
+/// an error, as happens near a point where the integral diverges.
+#[allow(clippy::too_many_arguments)]
+fn adapt<F>(integrand: &mut F, low: f64, high: f64, f_low: f64, f_middle: f64, f_high: f64, whole: f64, tolerance: f64, depth: usize) -> Result<f64, String>
+where
+    F: FnMut(f64) -> Result<f64, String>,
+{
+    let middle = (low + high) / 2.0;
+    let f_left = integrand((low + middle) / 2.0)?;
+    let f_right = integrand((middle + high) / 2.0)?;
+    let left = simpson(low, middle, f_low, f_left, f_middle);
+    let right = simpson(middle, high, f_middle, f_right, f_high);
+    let error = left + right - whole;
+    if error.abs() <= 15.0 * tolerance {
+        return Ok(left + right + error / 15.0);
+    }
+    if depth >= MAX_DEPTH {
+        return Err(String::from("integrate did not converge; the integral may not exist."));
+    }
+    let left = adapt(integrand, low, middle, f_low, f_left, f_middle, left, tolerance / 2.0, depth + 1)?;
+    let right = adapt(integrand, middle, high, f_middle, f_right, f_high, right, tolerance / 2.0, depth + 1)?;
+    return Ok(left + right);
+}
+
+fn simpson(low: f64, high: f64, f_low: f64, f_middle: f64, f_high: f64) -> f64 {
+    return (high - low) / 6.0 * (f_low + 4.0 * f_middle + f_high);
+}
+
+/// Private function that combines the values of an equation for every whole number from `low`
+/// to `high`, used for sum and product.
+#[allow(clippy::too_many_arguments)]
+fn accumulate<F>(body: &Expression, variable: &str, low: f64, high: f64, variables: &dyn Lookup, budget: &mut usize, empty: f64, combine: F) -> Result<f64, String>
+where
+    F: Fn(f64, f64) -> f64,
+{
+    if low.fract() != 0.0 || high.fract() != 0.0 {
+        return Err(String::from("Bounds of sum and product must be whole numbers."));
+    }
+    // Past 2^53 adding one no longer changes a float, so the loop would never end.
+    if high - low >= MAX_EVALUATIONS as f64 || low.abs().max(high.abs()) >= 9007199254740992.0 {
+        return Err(format!("Too many terms: {} to {} is more than {}.", low, high, MAX_EVALUATIONS));
+    }
+    // Sums inside other sums share the budget, which the terms may no longer fit in.
+    if high - low >= *budget as f64 {
+        return Err(format!("Gave up after {} evaluations.", MAX_EVALUATIONS));
+    }
+    let mut ret = empty;
+    let mut k = low;
+    while k <= high {
+        ret = combine(ret, evaluate_at(body, variable, k, variables, budget)?);
+        k += 1.0;
+    }
+    return Ok(ret);
+}
+
+/// Private function that evaluates an equation with one variable bound to a value.
+fn evaluate_at(body: &Expression, variable: &str, value: f64, variables: &dyn Lookup, budget: &mut usize) -> Result<f64, String> {
+    if *budget == 0 {
+        return Err(format!("Gave up after {} evaluations.", MAX_EVALUATIONS));
+    }
+    *budget -= 1;
+    let scope = Scope::new(variables, variable, value);
+    return value::evaluate(body, &scope, budget)?.number();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calculator;
+    use prefix;
+    use std::f64::consts::PI;
+
+    fn run(input: &str, map: &HashMap<String, f64>) -> Result<f64, String> {
+        let vec = calculator::parse_to_vec(&prefix::translate_infix(input).unwrap()).unwrap();
+        return calculator::calculate(&vec, map).map(|result| match result[0] {
+            calculator::Cell::Value(val) => val,
+            _ => f64::NAN,
+        });
+    }
+
+    fn assert_close(expected: f64, input: &str) {
+        let actual = run(input, &HashMap::<String, f64>::new()).unwrap();
+        assert!((expected - actual).abs() < 1e-8, "{} gave {}", input, actual);
+    }
+
+    #[test]
+    fn test_sum_and_product() {
+        assert_close(338350.0, "sum(k^2, k, 1, 100)");
+        assert_close(120.0, "product(k, k, 1, 5)");
+        assert_close(0.0, "sum(k, k, 1, 0)");
+        assert_close(1.0, "product(k, k, 1, 0)");
+        assert_close(36.0, "sum(sum(j * k, j, 1, 3), k, 1, 3)");
+    }
+
+    #[test]
+    fn test_integrate() {
+        assert_close(2.0, "integrate(sin(x), x, 0, pi)".replace("pi", &PI.to_string()).as_str());
+        assert_close(1.0 / 3.0, "integrate(x^2, x, 0, 1)");
+        assert_close(-1.0 / 3.0, "integrate(x^2, x, 1, 0)");
+        assert_close(2.0 / 3.0, "integrate(sqrt(x), x, 0, 1)");
+        assert_close(1.0 - (-1.0f64).exp(), "integrate(exp(-t), t, 0, 1)");
+    }
+
+    #[test]
+    fn test_scoped_binding() {
+        let mut map = HashMap::<String, f64>::new();
+        map.insert(String::from("k"), 100.0);
+        map.insert(String::from("a"), 2.0);
+        assert_eq!(Ok(12.0), run("sum(a * k, k, 1, 3)", &map));
+        assert_eq!(Some(&100.0), map.get("k"));
+        assert_eq!(Ok(106.0), run("k + sum(k, k, 1, 3)", &map));
+    }
+
+    #[test]
+    fn test_errors() {
+        let map = HashMap::<String, f64>::new();
+        assert_eq!(
+            Err(String::from("Bounds of sum and product must be whole numbers.")),
+            run("sum(k, k, 1, 2.5)", &map)
+        );
+        assert_eq!(Err(String::from("Too many terms: 1 to 1000000000000 is more than 1000000.")), run("sum(k, k, 1, 1e12)", &map));
+        assert_eq!(Err(String::from("Gave up after 1000000 evaluations.")), run("sum(sum(j, j, 1, 1000), k, 1, 1000)", &map));
+        assert!(run("integrate(1 / x, x, -1, 1)", &map).is_err());
+        assert!(run("integrate(1 / x, x, -1, 2)", &map).unwrap_err().starts_with("integrate did not converge"));
+        assert!(run("sum(k, 2, 1, 3)", &map).is_err());
+        assert_eq!(Err(String::from("Variable y does not have a defined value.")), run("sum(y, k, 1, 3)", &map));
+    }
+}
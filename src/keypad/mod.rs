@@ -0,0 +1,337 @@
+#![allow(clippy::needless_return)]
+
+use serde::Deserialize;
+use serde_json;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Struct that describes an on-screen keypad: a name and rows of keys.
+/// A layout is written in JSON. A key is either the text it types, or an object with a label
+/// and one of insert (text to type), action (evaluate, clear, backspace, previous or next) or
+/// command (an entry to evaluate on its own, e.g. "m+").
+/// ```text
+/// {"name": "tiny", "rows": [["7", "8", {"label": "÷", "insert": "/"}],
+///                           [{"label": "=", "action": "evaluate"}]]}
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Layout {
+    pub name: String,
+    pub rows: Vec<Vec<Key>>,
+}
+
+/// Struct that describes one key: what it shows and what pressing it does.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "KeySpec")]
+pub struct Key {
+    pub label: String,
+    pub action: KeyAction,
+}
+
+/// Enum that defines what a key does:
+/// * Insert [Types text at the caret, replacing any selection]
+/// * Evaluate [Evaluates the input]
+/// * Clear [Empties the input]
+/// * Backspace [Deletes the selection, or the character before the caret]
+/// * Previous [Recalls the line entered before]
+/// * Next [Recalls the line entered after, or what was being typed]
+/// * Command [Evaluates its own text as an entry, leaving the input alone]
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyAction {
+    Insert(String),
+    Evaluate,
+    Clear,
+    Backspace,
+    Previous,
+    Next,
+    Command(String),
+}
+
+/// Function that returns the action with a name, e.g. evaluate, or None when there is none.
+pub fn parse_action(name: &str) -> Option<KeyAction> {
+    match name {
+        "evaluate" => return Some(KeyAction::Evaluate),
+        "clear" => return Some(KeyAction::Clear),
+        "backspace" => return Some(KeyAction::Backspace),
+        "previous" => return Some(KeyAction::Previous),
+        "next" => return Some(KeyAction::Next),
+        _ => return None,
+    }
+}
+
+/// Private enum for the two ways a key may be written in JSON.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeySpec {
+    Text(String),
+    Full {
+        label: String,
+        insert: Option<String>,
+        action: Option<String>,
+        command: Option<String>,
+    },
+}
+
+impl TryFrom<KeySpec> for Key {
+    type Error = String;
+
+    fn try_from(spec: KeySpec) -> Result<Key, String> {
+        match spec {
+            KeySpec::Text(text) => {
+                return Ok(Key { label: text.clone(), action: KeyAction::Insert(text) });
+            }
+            KeySpec::Full { label, insert, action, command } => {
+                let action = match (insert, action, command) {
+                    (Some(text), None, None) => KeyAction::Insert(text),
+                    (None, Some(action), None) => match parse_action(&action) {
+                        Some(action) => action,
+                        None => return Err(format!("Key '{}' has unknown action '{}'; {}", label, action, ACTIONS)),
+                    },
+                    (None, None, Some(command)) => KeyAction::Command(command),
+                    (None, None, None) => KeyAction::Insert(label.clone()),
+                    _ => return Err(format!("Key '{}' may have only one of insert, action and command.", label)),
+                };
+                return Ok(Key { label, action });
+            }
+        }
+    }
+}
+
+impl Layout {
+    /// Function that returns every key, row by row, so that a key can be found by its position.
+    pub fn keys(&self) -> Vec<&Key> {
+        return self.rows.iter().flat_map(|row| row.iter()).collect();
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        return Bindings::new();
+    }
+}
+
+const ACTIONS: &str = "use evaluate, clear, backspace, previous or next.";
+
+/// Struct that maps keyboard shortcuts to actions. A shortcut is the key's name as
+/// KeyboardEvent.key gives it, after the modifiers held with it, e.g. Enter, ArrowUp or Ctrl+l.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bindings {
+    keys: HashMap<String, KeyAction>,
+}
+
+impl Bindings {
+    /// Creates the usual bindings: Enter evaluates, Escape clears, and the up and down arrows
+    /// recall earlier lines.
+    pub fn new() -> Bindings {
+        let mut keys = HashMap::<String, KeyAction>::new();
+        keys.insert(String::from("Enter"), KeyAction::Evaluate);
+        keys.insert(String::from("Escape"), KeyAction::Clear);
+        keys.insert(String::from("ArrowUp"), KeyAction::Previous);
+        keys.insert(String::from("ArrowDown"), KeyAction::Next);
+        return Bindings { keys };
+    }
+
+    /// Function that binds a shortcut to the action with a name, replacing what it did before.
+    pub fn bind(&mut self, shortcut: &str, action: &str) -> Result<(), String> {
+        let shortcut = normalize_shortcut(shortcut)?;
+        let action = parse_action(action).ok_or_else(|| format!("Unknown action '{}'; {}", action, ACTIONS))?;
+        self.keys.insert(shortcut, action);
+        return Ok(());
+    }
+
+    /// Function that removes a shortcut, so that the key does what it does in any text input.
+    pub fn unbind(&mut self, shortcut: &str) -> Result<(), String> {
+        self.keys.remove(&normalize_shortcut(shortcut)?);
+        return Ok(());
+    }
+
+    /// Function that returns the action for a key pressed with the given modifiers.
+    pub fn action(&self, key: &str, ctrl: bool, alt: bool, shift: bool, meta: bool) -> Option<&KeyAction> {
+        let mut shortcut = String::new();
+        for (held, name) in [(ctrl, "Ctrl+"), (alt, "Alt+"), (meta, "Meta+")].iter() {
+            if *held {
+                shortcut.push_str(name);
+            }
+        }
+        // Shift already shows in the key of a character, e.g. + rather than =.
+        if shift && key.chars().count() > 1 {
+            shortcut.push_str("Shift+");
+        }
+        shortcut.push_str(key);
+        return self.keys.get(&normalize_shortcut(&shortcut).ok()?);
+    }
+}
+
+/// Private function that writes a shortcut's modifiers in one order and spelling, so that
+/// ctrl+shift+ArrowUp and Shift+Control+ArrowUp are the same shortcut.
+fn normalize_shortcut(shortcut: &str) -> Result<String, String> {
+    let mut held = [false; 4];
+    let mut rest = shortcut;
+    while let Some(position) = rest.find('+') {
+        // A trailing + is the key itself, as in Ctrl++.
+        if position + 1 == rest.len() {
+            break;
+        }
+        let index = match rest[..position].to_lowercase().as_str() {
+            "ctrl" | "control" => 0,
+            "alt" => 1,
+            "meta" | "cmd" => 2,
+            "shift" => 3,
+            _ => break,
+        };
+        held[index] = true;
+        rest = &rest[position + 1..];
+    }
+    if rest.is_empty() {
+        return Err(format!("Shortcut '{}' has no key.", shortcut));
+    }
+    let mut ret = String::new();
+    for (index, name) in ["Ctrl+", "Alt+", "Meta+", "Shift+"].iter().enumerate() {
+        if held[index] {
+            ret.push_str(name);
+        }
+    }
+    ret.push_str(rest);
+    return Ok(ret);
+}
+
+/// Layout with digits, the four operators and the memory keys.
+const BASIC: &str = r#"{"name": "basic", "rows": [
+    [{"label": "MC", "command": "mc"}, {"label": "MR", "command": "mr"}, {"label": "M+", "command": "m+"}, {"label": "M-", "command": "m-"}],
+    [{"label": "C", "action": "clear"}, {"label": "⌫", "action": "backspace"}, "(", ")"],
+    ["7", "8", "9", {"label": "÷", "insert": "/"}],
+    ["4", "5", "6", {"label": "×", "insert": "*"}],
+    ["1", "2", "3", "-"],
+    ["0", ".", {"label": "=", "action": "evaluate"}, "+"]
+]}"#;
+
+/// Layout that adds powers, functions, constants and numbered registers to the basic keys.
+const SCIENTIFIC: &str = r#"{"name": "scientific", "rows": [
+    [{"label": "MC", "command": "mc"}, {"label": "MR", "command": "mr"}, {"label": "M+", "command": "m+"}, {"label": "M-", "command": "m-"}, {"label": "STO", "insert": "sto "}, {"label": "RCL", "insert": "rcl "}],
+    [{"label": "sin", "insert": "sin("}, {"label": "cos", "insert": "cos("}, {"label": "tan", "insert": "tan("}, {"label": "C", "action": "clear"}, {"label": "⌫", "action": "backspace"}],
+    [{"label": "ln", "insert": "ln("}, {"label": "log", "insert": "log("}, {"label": "√", "insert": "sqrt("}, "(", ")"],
+    ["pi", "e", "7", "8", "9"],
+    ["^", ",", "4", "5", "6"],
+    [{"label": "x²", "insert": "^2"}, {"label": "÷", "insert": "/"}, "1", "2", "3"],
+    [{"label": "×", "insert": "*"}, "-", "0", ".", "+"],
+    [{"label": "=", "action": "evaluate"}]
+]}"#;
+
+/// Layout for whole numbers, comparisons and logic.
+const PROGRAMMER: &str = r#"{"name": "programmer", "rows": [
+    [{"label": "and", "insert": " and "}, {"label": "or", "insert": " or "}, {"label": "not", "insert": "not "}, {"label": "C", "action": "clear"}, {"label": "⌫", "action": "backspace"}],
+    ["==", "!=", "<", ">", "?"],
+    [":", {"label": "abs", "insert": "abs("}, {"label": "min", "insert": "min("}, {"label": "max", "insert": "max("}, ","],
+    ["7", "8", "9", "(", ")"],
+    ["4", "5", "6", {"label": "×", "insert": "*"}, {"label": "÷", "insert": "/"}],
+    ["1", "2", "3", "+", "-"],
+    ["0", "^", {"label": "=", "action": "evaluate"}]
+]}"#;
+
+/// Function that reads a layout from JSON.
+pub fn parse_layout(json: &str) -> Result<Layout, String> {
+    let ret = serde_json::from_str::<Layout>(json).map_err(|e| format!("Invalid keypad layout: {}", e))?;
+    if ret.rows.iter().all(|row| row.is_empty()) {
+        return Err(String::from("Invalid keypad layout: it has no keys."));
+    }
+    return Ok(ret);
+}
+
+/// Function that returns a built-in layout (basic, scientific or programmer) by name.
+pub fn builtin_layout(name: &str) -> Option<Layout> {
+    let json = match name {
+        "basic" => BASIC,
+        "scientific" => SCIENTIFIC,
+        "programmer" => PROGRAMMER,
+        _ => return None,
+    };
+    return Some(parse_layout(json).unwrap());
+}
+
+/// Function that replaces the text selected from `start` to `end` (together with `before`
+/// characters in front of it when nothing is selected) with `text`, returning the new value and
+/// the caret after the inserted text. Positions count UTF-16 units, as the browser does.
+/// # Example
+/// ("2+3", 1, 1, 0, "0") -> ("20+3", 2)
+/// ("2+3", 3, 3, 1, "") -> ("2+", 2)
+/// ...
+pub fn replace_selection(value: &str, start: u32, end: u32, before: u32, text: &str) -> (String, u32) {
+    let units = value.encode_utf16().collect::<Vec<u16>>();
+    let end = (end as usize).min(units.len());
+    let mut start = (start as usize).min(end);
+    if start == end {
+        start = start.saturating_sub(before as usize);
+        // Never split a surrogate pair.
+        if start > 0 && start < end && (0xDC00..0xE000).contains(&units[start]) {
+            start -= 1;
+        }
+    }
+    let mut ret = units[..start].to_vec();
+    ret.extend(text.encode_utf16());
+    let caret = ret.len() as u32;
+    ret.extend_from_slice(&units[end..]);
+    return (String::from_utf16_lossy(&ret), caret);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_layouts() {
+        for name in ["basic", "scientific", "programmer"].iter() {
+            let layout = builtin_layout(name).unwrap();
+            assert_eq!(*name, layout.name);
+            assert!(layout.keys().iter().any(|key| key.action == KeyAction::Evaluate));
+        }
+        let basic = builtin_layout("basic").unwrap();
+        assert!(basic.keys().iter().any(|key| key.action == KeyAction::Command(String::from("m+"))));
+        assert_eq!(None, builtin_layout("abacus"));
+    }
+
+    #[test]
+    fn test_parse_layout() {
+        let layout = parse_layout(r#"{"name": "tiny", "rows": [["7", {"label": "÷", "insert": "/"}], [{"label": "M+", "command": "m+"}, {"label": "x"}]]}"#).unwrap();
+        let keys = layout.keys();
+        assert_eq!(KeyAction::Insert(String::from("7")), keys[0].action);
+        assert_eq!(KeyAction::Insert(String::from("/")), keys[1].action);
+        assert_eq!(KeyAction::Command(String::from("m+")), keys[2].action);
+        assert_eq!(KeyAction::Insert(String::from("x")), keys[3].action);
+    }
+
+    #[test]
+    fn test_parse_layout_errors() {
+        assert!(parse_layout(r#"{"name": "bad", "rows": [[{"label": "?", "action": "explode"}]]}"#)
+            .unwrap_err()
+            .contains("unknown action 'explode'"));
+        assert!(parse_layout(r#"{"name": "bad", "rows": [[{"label": "?", "insert": "1", "action": "clear"}]]}"#)
+            .unwrap_err()
+            .contains("only one of"));
+        assert!(parse_layout(r#"{"name": "empty", "rows": [[]]}"#).is_err());
+        assert!(parse_layout("[").is_err());
+    }
+
+    #[test]
+    fn test_bindings() {
+        let mut bindings = Bindings::new();
+        assert_eq!(Some(&KeyAction::Evaluate), bindings.action("Enter", false, false, false, false));
+        assert_eq!(Some(&KeyAction::Previous), bindings.action("ArrowUp", false, false, false, false));
+        assert_eq!(None, bindings.action("ArrowUp", true, false, false, false));
+        bindings.bind("shift+control+ArrowUp", "previous").unwrap();
+        assert_eq!(Some(&KeyAction::Previous), bindings.action("ArrowUp", true, false, true, false));
+        bindings.bind("Ctrl++", "clear").unwrap();
+        assert_eq!(Some(&KeyAction::Clear), bindings.action("+", true, false, true, false));
+        bindings.unbind("Enter").unwrap();
+        assert_eq!(None, bindings.action("Enter", false, false, false, false));
+        assert!(bindings.bind("Ctrl+k", "launch").unwrap_err().starts_with("Unknown action 'launch'"));
+        assert!(bindings.bind("", "clear").is_err());
+    }
+
+    #[test]
+    fn test_replace_selection() {
+        assert_eq!((String::from("20+3"), 2), replace_selection("2+3", 1, 1, 0, "0"));
+        assert_eq!((String::from("2+"), 2), replace_selection("2+3", 3, 3, 1, ""));
+        assert_eq!((String::from("sin(3"), 4), replace_selection("2+3", 0, 2, 1, "sin("));
+        assert_eq!((String::from("3"), 0), replace_selection("3", 0, 0, 1, ""));
+        assert_eq!((String::from("a"), 1), replace_selection("a😀", 3, 3, 1, ""));
+    }
+}
@@ -1,127 +1,1169 @@
 extern crate regex;
-extern crate unicode_segmentation;
+extern crate serde;
+extern crate serde_json;
 extern crate wasm_bindgen;
+extern crate web_sys;
 
-mod calculator;
-mod infix;
-mod prefix;
+pub mod calculator;
+pub mod complete;
+pub mod date;
+pub mod derivative;
+pub mod expression;
+pub mod highlight;
+pub mod infix;
+pub mod integrate;
+pub mod keypad;
+pub mod linear;
+pub mod memory;
+pub mod postfix;
+pub mod prefix;
+pub mod session;
+pub mod simplify;
+pub mod solve;
+pub mod stack;
+pub mod statistics;
+pub mod token;
+pub mod validate;
+pub mod value;
+pub mod widget;
 use std::collections::HashMap;
 
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
+/// Enum that selects the notation used to read input and to display equations.
 #[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Notation {
+    Infix,
+    Prefix,
+    Postfix,
+}
+
+/// Struct that holds a derivative in both infix and prefix notation.
+#[wasm_bindgen]
+pub struct Derivative {
+    infix: String,
+    prefix: String
+}
+
+#[allow(clippy::needless_return)]
+#[wasm_bindgen]
+impl Derivative {
+    #[wasm_bindgen(getter)]
+    pub fn infix(&self) -> String {
+        return self.infix.clone();
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn prefix(&self) -> String {
+        return self.prefix.clone();
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
 pub struct Calc {
     equation: Vec<calculator::Cell>,
-    variables: HashMap<String, f64>
+    variables: HashMap<String, f64>,
+    lists: HashMap<String, Vec<f64>>,
+    notation: Notation,
+    stack: stack::Stack,
+    stack_mode: bool,
+    store_solutions: bool,
+    infix_options: prefix::InfixOptions,
+    formats: date::Formats,
+    clock: Option<f64>,
+    session: session::History,
+    memory: memory::Memory,
+    answer: Option<f64>,
+    elements: Elements
+}
+
+/// Struct that holds the ids of the page elements a calculator reads from and writes to.
+#[derive(Clone)]
+struct Elements {
+    input: String,
+    output: String,
+    history: String
 }
 
+#[allow(clippy::needless_return)]
+impl Default for Calc {
+    fn default() -> Calc {
+        return Calc::new();
+    }
+}
+
+#[allow(clippy::needless_return)]
 #[wasm_bindgen]
 impl Calc {
+    /// Creates a calculator with no variables set; pi and e are built-in constants. `calc` uses
+    /// the elements with ids input, output and inputHistory.
     #[wasm_bindgen(constructor)]
     pub fn new() -> Calc {
         return Calc {
             equation: Vec::<calculator::Cell>::new(),
-            variables: HashMap::<String, f64>::new()
+            variables: HashMap::<String, f64>::new(),
+            lists: HashMap::<String, Vec<f64>>::new(),
+            notation: Notation::Infix,
+            stack: stack::Stack::new(),
+            stack_mode: false,
+            store_solutions: false,
+            infix_options: prefix::InfixOptions::default(),
+            formats: date::Formats::default(),
+            clock: None,
+            session: session::History::new(),
+            memory: memory::Memory::new(),
+            answer: None,
+            elements: Elements {
+                input: String::from("input"),
+                output: String::from("output"),
+                history: String::from("inputHistory")
+            }
         }
     }
 
+    /// Creates a calculator that reads equations from the text input with id `input_id`, shows
+    /// answers in the text input with id `output_id` and keeps its history in the text area with
+    /// id `history_id`, so that several calculators can share a page. Fails if an element is
+    /// missing or of the wrong kind.
+    #[wasm_bindgen]
+    pub fn attach(input_id: &str, output_id: &str, history_id: &str) -> Result<Calc, JsValue> {
+        let document = get_document()?;
+        get_input_element(&document, input_id)?;
+        get_input_element(&document, output_id)?;
+        get_textarea_element(&document, history_id)?;
+        let mut ret = Calc::new();
+        ret.elements = Elements {
+            input: String::from(input_id),
+            output: String::from(output_id),
+            history: String::from(history_id)
+        };
+        return Ok(ret);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn notation(&self) -> Notation {
+        return self.notation;
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_notation(&mut self, notation: Notation) {
+        self.notation = notation;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn implicit_multiplication(&self) -> bool {
+        return self.infix_options.implicit_multiplication;
+    }
+
+    /// Switches multiplication of juxtaposed infix operands, e.g. 2pi or 3(a + b), on or off.
+    #[wasm_bindgen(setter)]
+    pub fn set_implicit_multiplication(&mut self, implicit_multiplication: bool) {
+        self.infix_options.implicit_multiplication = implicit_multiplication;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn implicit_binds_tighter(&self) -> bool {
+        return self.infix_options.implicit_binds_tighter;
+    }
+
+    /// Selects whether implicit multiplication binds tighter than '*' and '/' (1/2x is 1/(2x))
+    /// or the same (1/2x is (1/2)x).
+    #[wasm_bindgen(setter)]
+    pub fn set_implicit_binds_tighter(&mut self, implicit_binds_tighter: bool) {
+        self.infix_options.implicit_binds_tighter = implicit_binds_tighter;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn date_format(&self) -> date::DateFormat {
+        return self.formats.date;
+    }
+
+    /// Selects how dates are shown: as written (2026-10-18), long (18 October 2026) or US
+    /// style (10/18/2026).
+    #[wasm_bindgen(setter)]
+    pub fn set_date_format(&mut self, date_format: date::DateFormat) {
+        self.formats.date = date_format;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn duration_format(&self) -> date::DurationFormat {
+        return self.formats.duration;
+    }
+
+    /// Selects how durations are shown: in words (1 day 2 hours), short (1d 2h) or as a clock
+    /// (26:00:00).
+    #[wasm_bindgen(setter)]
+    pub fn set_duration_format(&mut self, duration_format: date::DurationFormat) {
+        self.formats.duration = duration_format;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn stack_mode(&self) -> bool {
+        return self.stack_mode;
+    }
+
+    /// Switches `calc` between evaluating equations and driving the reverse Polish value stack.
+    #[wasm_bindgen(setter)]
+    pub fn set_stack_mode(&mut self, stack_mode: bool) {
+        self.stack_mode = stack_mode;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn store_solutions(&self) -> bool {
+        return self.store_solutions;
+    }
+
+    /// Selects whether solving a system of equations also sets each variable to its answer.
+    #[wasm_bindgen(setter)]
+    pub fn set_store_solutions(&mut self, store_solutions: bool) {
+        self.store_solutions = store_solutions;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn clock(&self) -> Option<f64> {
+        return self.clock;
+    }
+
+    /// Sets the time `now` stands for, in milliseconds since 1970-01-01 UTC as Date.now() gives
+    /// it. Hosts and tests that want repeatable answers pin it; undefined goes back to the
+    /// host's clock.
+    #[wasm_bindgen(setter)]
+    pub fn set_clock(&mut self, clock: Option<f64>) {
+        self.clock = clock;
+    }
+
+    /// Returns the lines entered in this session, oldest first, whether or not they worked.
+    #[wasm_bindgen]
+    pub fn inputs(&self) -> Vec<String> {
+        return self.session.entries().to_vec();
+    }
+
+    /// Recalls the line entered before the one last recalled, starting from the newest;
+    /// `current` is what is being typed, which `next_input` gives back after the newest line.
+    /// Returns nothing at the oldest line.
+    #[wasm_bindgen]
+    pub fn previous_input(&mut self, current: &str) -> Option<String> {
+        return self.session.recall_previous(current);
+    }
+
+    /// Recalls the line entered after the one last recalled, or what was being typed before
+    /// recalling began. Returns nothing when no line is being recalled.
+    #[wasm_bindgen]
+    pub fn next_input(&mut self) -> Option<String> {
+        return self.session.recall_next();
+    }
+
+    /// Returns the answer the input would give, or the error it would raise, without setting
+    /// variables or adding to the history, so that it can be shown while the input is typed.
+    #[wasm_bindgen]
+    pub fn preview(&self, input: &str) -> Result<String, JsValue> {
+        return self.dry_run(input).map(|entry| entry.answer).map_err(|e| JsValue::from_str(&e));
+    }
+
+    /// Returns the variables, lists, constants and functions whose names complete the name that
+    /// ends at `caret`, counted in characters, each with its kind and its value or how it is
+    /// called.
+    #[wasm_bindgen]
+    pub fn completions(&self, input: &str, caret: usize) -> Vec<complete::Completion> {
+        return complete::complete(input, caret, &self.variables, &self.lists);
+    }
+
+    /// Returns the variables and lists that are set, in alphabetical order.
+    #[wasm_bindgen]
+    pub fn variables(&self) -> Vec<Variable> {
+        let mut ret = Vec::<Variable>::new();
+        for (name, value) in self.variables.iter() {
+            let value = value::print_value(&value::Value::Number(*value));
+            ret.push(Variable { name: name.clone(), value, kind: "number" });
+        }
+        for (name, values) in self.lists.iter() {
+            let value = value::print_value(&value::Value::Vector(values.clone()));
+            ret.push(Variable { name: name.clone(), value, kind: "list" });
+        }
+        ret.sort_by(|a, b| a.name.cmp(&b.name));
+        return ret;
+    }
+
+    /// Sets a variable to what an equation in the current notation gives, e.g. 1/3 or
+    /// [1, 2, 3], which makes it a number or a list.
+    #[wasm_bindgen]
+    pub fn set_variable(&mut self, name: &str, value: &str) -> Result<(), JsValue> {
+        return self.assign_variable(name, value).map_err(|e| JsValue::from_str(&e));
+    }
+
+    /// Removes a variable or list. Returns whether there was one.
+    #[wasm_bindgen]
+    pub fn delete_variable(&mut self, name: &str) -> bool {
+        return self.variables.remove(name).is_some() || self.lists.remove(name).is_some();
+    }
+
+    /// Gives a variable or list another name, in the current equation as well.
+    #[wasm_bindgen]
+    pub fn rename_variable(&mut self, name: &str, new_name: &str) -> Result<(), JsValue> {
+        return self.rename(name, new_name).map_err(|e| JsValue::from_str(&e));
+    }
+
+    /// Returns the memory that m+, m-, mr and mc work on.
+    #[wasm_bindgen(getter)]
+    pub fn memory(&self) -> f64 {
+        return self.memory.register;
+    }
+
+    /// Returns what `sto` put in a numbered register, if anything.
+    #[wasm_bindgen]
+    pub fn register(&self, register: usize) -> Option<f64> {
+        return self.memory.registers.get(&register).cloned();
+    }
+
+    /// Returns the variables, lists, lines entered and memory as JSON, for `restore_session`.
+    #[wasm_bindgen]
+    pub fn save_session(&self) -> String {
+        // JSON has no infinities or NaN, so variables, lists and registers holding them are left
+        // out, and such a memory is saved as cleared.
+        let mut memory = self.memory.clone();
+        if !memory.register.is_finite() {
+            memory.register = 0.0;
+        }
+        memory.registers.retain(|_, value| value.is_finite());
+        let saved = session::Saved {
+            variables: self.variables.iter().filter(|(_, value)| value.is_finite()).map(|(k, v)| (k.clone(), *v)).collect(),
+            lists: self.lists.iter().filter(|(_, values)| values.iter().all(|value| value.is_finite())).map(|(k, v)| (k.clone(), v.clone())).collect(),
+            inputs: self.session.entries().to_vec(),
+            memory,
+        };
+        return saved.to_json();
+    }
+
+    /// Replaces the variables, lists, lines entered and memory with those of a saved session.
+    #[wasm_bindgen]
+    pub fn restore_session(&mut self, json: &str) -> Result<(), JsValue> {
+        return self.restore(json).map_err(|e| JsValue::from_str(&e));
+    }
+
+    /// Stores a list of numbers pasted from a spreadsheet under a name, so that it can be used in
+    /// equations, e.g. mean(data). Returns how many numbers were read.
+    #[wasm_bindgen]
+    pub fn set_list(&mut self, name: &str, input: &str) -> Result<usize, JsValue> {
+        let values = calculator::parse_list(input).map_err(|e| JsValue::from_str(&e))?;
+        let count = values.len();
+        self.variables.remove(name);
+        self.lists.insert(String::from(name), values);
+        return Ok(count);
+    }
+
+    /// Returns the named list, if there is one.
+    #[wasm_bindgen]
+    pub fn list(&self, name: &str) -> Option<Vec<f64>> {
+        return self.lists.get(name).cloned();
+    }
+
+    /// Returns the values on the stack, bottom first.
+    #[wasm_bindgen]
+    pub fn stack(&self) -> Vec<f64> {
+        return self.stack.values().to_vec();
+    }
+
+    /// Applies one entry (values, operators and the commands dup, swap, drop, roll, clear and
+    /// undo) to the stack and returns the resulting stack, bottom first.
+    #[wasm_bindgen]
+    pub fn stack_execute(&mut self, entry: &str) -> Result<Vec<f64>, JsValue> {
+        self.stack.execute(entry, &self.variables).map_err(|e| JsValue::from_str(&e))?;
+        return Ok(self.stack());
+    }
+
+    /// Returns the input with every unclosed bracket closed, or nothing when the input is
+    /// balanced or cannot be fixed by appending brackets.
+    #[wasm_bindgen]
+    pub fn auto_close(&self, input: &str) -> Option<String> {
+        return validate::auto_close(input);
+    }
+
+    /// Simplifies an equation written in the current input notation, using the values of any
+    /// variables that have been set, and returns it in infix notation.
+    #[wasm_bindgen]
+    pub fn simplify(&self, input: &str) -> Result<String, JsValue> {
+        let prefix_notation = self.translate_to_prefix(input.trim()).map_err(|e| JsValue::from_str(&e))?;
+        let expression = expression::from_prefix(&prefix_notation).map_err(|e| JsValue::from_str(&e))?;
+        return Ok(simplify::simplify_to_infix(&expression, &self.variables));
+    }
+
+    /// Differentiates an equation written in the current input notation with respect to a
+    /// variable and simplifies the result.
+    #[wasm_bindgen]
+    pub fn diff(&self, input: &str, variable: &str) -> Result<Derivative, JsValue> {
+        let prefix_notation = self.translate_to_prefix(input.trim()).map_err(|e| JsValue::from_str(&e))?;
+        let expression = expression::from_prefix(&prefix_notation).map_err(|e| JsValue::from_str(&e))?;
+        return self.differentiate(&expression, variable).map_err(|e| JsValue::from_str(&e));
+    }
+
+    /// Numerically solves an equation written in the current input notation for a variable, using
+    /// the stored variables for every other name. Without a guess every root between -100 and 100
+    /// is returned; with one, only the root nearest to it.
+    #[wasm_bindgen]
+    pub fn solve(&self, input: &str, variable: &str, guess: Option<f64>) -> Result<Vec<f64>, JsValue> {
+        let prefix_notation = self.translate_to_prefix(input.trim()).map_err(|e| JsValue::from_str(&e))?;
+        let expression = expression::from_prefix(&prefix_notation).map_err(|e| JsValue::from_str(&e))?;
+        return solve::solve(&expression, variable, guess, &self.variables).map_err(|e| JsValue::from_str(&e.message()));
+    }
+
+    /// Solves a list of linear equations, e.g. [2x + y = 5, x - y = 1], for a list of variables,
+    /// e.g. [x, y], both written in the current input notation. The answers are in the order of
+    /// the variables and are stored as variables when `store_solutions` is set.
+    #[wasm_bindgen]
+    pub fn solve_system(&mut self, equations: &str, variables: &str) -> Result<Vec<f64>, JsValue> {
+        let mut args = Vec::<expression::Expression>::new();
+        for input in [equations, variables].iter() {
+            let prefix_notation = self.translate_to_prefix(input.trim()).map_err(|e| JsValue::from_str(&e))?;
+            args.push(expression::from_prefix(&prefix_notation).map_err(|e| JsValue::from_str(&e))?);
+        }
+        let answers = self.solve_system_call(&args).map_err(|e| JsValue::from_str(&e))?;
+        return Ok(answers.into_iter().map(|answer| answer.1).collect());
+    }
+
+    /// Converts an equation written in the current input notation to infix notation.
+    #[wasm_bindgen]
+    pub fn to_infix(&self, input: &str) -> Result<String, JsValue> {
+        let prefix_notation = self.translate_to_prefix(input.trim()).map_err(|e| JsValue::from_str(&e))?;
+        return infix::translate_prefix(&prefix_notation).map_err(|e| JsValue::from_str(&e));
+    }
+
+    /// Converts an equation written in the current input notation to prefix notation.
+    #[wasm_bindgen]
+    pub fn to_prefix(&self, input: &str) -> Result<String, JsValue> {
+        let prefix_notation = self.translate_to_prefix(input.trim()).map_err(|e| JsValue::from_str(&e))?;
+        let expression = expression::from_prefix(&prefix_notation).map_err(|e| JsValue::from_str(&e))?;
+        return Ok(prefix::print_prefix(&expression));
+    }
+
+    /// Converts an equation written in the current input notation to postfix notation.
+    #[wasm_bindgen]
+    pub fn to_postfix(&self, input: &str) -> Result<String, JsValue> {
+        let prefix_notation = self.translate_to_prefix(input.trim()).map_err(|e| JsValue::from_str(&e))?;
+        return postfix::translate_prefix(&prefix_notation).map_err(|e| JsValue::from_str(&e));
+    }
+
+    /// Reads the equation in the input box, evaluates it and shows the answer in the output box,
+    /// adding the input, or the error, to the history.
     #[wasm_bindgen]
     pub fn calc(&mut self) -> Result<(), JsValue> {
-        let window = web_sys::window().expect("no global `window` exists");
-        let document = window.document().expect("should have a document on window");
-
-        let infix_notation_control = unwrap_html_input_element(document.get_element_by_id("input").unwrap());
-        let infix_notation = infix_notation_control.value();
-        let processed_input = prefix::translate_infix(infix_notation.trim());
-        let vector_and_map = calculator::parse_to_vec_and_map(processed_input.as_str());
-        if vector_and_map.is_err() {
-            let input_history = unwrap_html_textarea_element(document.get_element_by_id("inputHistory").unwrap());
-            if  input_history.selection_end().unwrap() == input_history.selection_start().unwrap() {
-                input_history.set_scroll_top(input_history.scroll_height());
-            }
-            let mut ret = String::new();
-            ret.push_str(&input_history.value());
-            ret.push_str("Error parsing '");
-            ret.push_str(&infix_notation);
-            ret.push_str("': \n");
-            ret.push_str(&vector_and_map.err().unwrap());
-            ret.push_str("\n");
-            input_history.set_value(&ret);
-            return Ok(());
-        } else {
-            let (eq, mut var) = vector_and_map.ok().unwrap();
-            if eq.is_empty() {
-                if var.is_empty() {
-                    let input_history = unwrap_html_textarea_element(document.get_element_by_id("inputHistory").unwrap());
-                    if  input_history.selection_end().unwrap() == input_history.selection_start().unwrap() {
-                        input_history.set_scroll_top(input_history.scroll_height());
-                    }
-                    let mut ret = String::new();
-                    ret.push_str(&input_history.value());
-                    ret.push_str("Input: ");
-                    ret.push_str(&infix_notation);
-                    ret.push_str("\n");
-                    ret.push_str("Neither equation nor variable set.\n");
-                    input_history.set_value(&ret);
-                    return Ok(());
-                } else {
-                    for (k, v) in var.drain().take(1) {
-                        self.variables.insert(k, v);
-                    }
-                }
-            } else {
-                self.equation.clear();
-                let mut iter = eq.iter();
-                loop {
-                    match iter.next() {
-                        Some(element) => {
-                            self.equation.push(element.clone());
-                        }
-                        None => {
-                            break;
-                        }
-                    }
+        let document = get_document()?;
+        let input_notation_control = get_input_element(&document, &self.elements.input)?;
+        let input_notation = input_notation_control.value();
+        let output = get_input_element(&document, &self.elements.output)?;
+        let input_history = get_textarea_element(&document, &self.elements.history)?;
+        return show_entry(&output, &input_history, self.evaluate(&input_notation));
+    }
+
+    /// Builds a calculator inside `container` (an answer box, a history, an input box and a
+    /// keypad) and hands this calculator to it. Enter or the keypad's = key evaluates the input and
+    /// the up and down arrows recall earlier lines; `Widget::bind_key` changes the shortcuts.
+    #[wasm_bindgen]
+    pub fn mount(self, container: &web_sys::Element) -> Result<widget::Widget, JsValue> {
+        return widget::Widget::mount(self, container);
+    }
+}
+
+/// Struct that describes a variable for display: its name, its value as the calculator prints
+/// it and its kind, number or list.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    name: String,
+    value: String,
+    kind: &'static str,
+}
+
+#[allow(clippy::needless_return)]
+#[wasm_bindgen]
+impl Variable {
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        return self.name.clone();
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn value(&self) -> String {
+        return self.value.clone();
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        return String::from(self.kind);
+    }
+}
+
+/// Struct that holds what one line of input gave: the answer to show and the lines to add to
+/// the history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub answer: String,
+    pub history: String,
+}
+
+#[allow(clippy::needless_return)]
+impl Calc {
+    /// Evaluates one line of input written in the current notation, or applies it to the stack
+    /// in stack mode, without touching the page. Errors say which step failed, e.g.
+    /// "Error from calculator:...", as the history shows them.
+    pub fn evaluate(&mut self, input: &str) -> Result<Entry, String> {
+        self.session.push(input);
+        if let Some(command) = memory::parse_command(input) {
+            return self.evaluate_memory(input, command.map_err(|e| format!("Error from memory: {}", e))?);
+        }
+        if self.stack_mode {
+            return self.evaluate_stack(input);
+        }
+        let processed_input = self.translate_to_prefix(input.trim());
+        if let Ok(ref prefix_notation) = processed_input {
+            if let Some(result) = self.assign_list(prefix_notation) {
+                let answer = result.map_err(|e| format!("Error from calculator:{}", e))?;
+                return Ok(Entry { answer, history: format!("{}\n", input) });
+            }
+        }
+        let (eq, mut var) = processed_input
+            .and_then(|prefix_notation| calculator::parse_to_vec_and_map(&prefix_notation))
+            .map_err(|e| format!("Error parsing '{}': \n{}", input, e))?;
+        if let Some(calculator::Cell::Function(ref name, _)) = eq.first() {
+            if name == "diff" || name == "solve" {
+                return self.evaluate_symbolic(input, &eq);
+            }
+        }
+        if eq.is_empty() {
+            if var.is_empty() {
+                return Err(format!("Input: {}\nNeither equation nor variable set.", input));
+            }
+            for (k, v) in var.drain().take(1) {
+                check_name(&k).map_err(|e| format!("Error from calculator:{}", e))?;
+                self.lists.remove(&k);
+                self.variables.insert(k, v);
+                if self.equation.is_empty() {
+                    // With no equation to recalculate, the answer is the value just set.
+                    self.answer = Some(v);
+                    return Ok(Entry { answer: value::print_value(&value::Value::Number(v)), history: format!("{}\n", input) });
                 }
             }
+        } else {
+            self.equation = eq;
+        }
+
+        let result = value::calculate_with(&self.equation, &self.scope()).map_err(|e| format!("Error from calculator:{}", e))?;
+        if let value::Value::Number(number) = result {
+            self.answer = Some(number);
+        }
+        let answer = value::print_value_with(&result, &self.formats);
+        let mut history = format!("{}\n", input);
+        if answer.starts_with('[') {
+            // Vectors and matrices are too wide to read in the answer box alone.
+            history.push_str(&format!("= {}\n", answer));
+        }
+        return Ok(Entry { answer, history });
+    }
+
+    /// Evaluates one line of input as `evaluate` does, but on a copy of the calculator, so that
+    /// variables, lists, the stack, the equation and the session are left as they were.
+    pub fn dry_run(&self, input: &str) -> Result<Entry, String> {
+        let mut scratch = self.clone();
+        return scratch.evaluate(input);
+    }
+
+    /// Sets a variable as `set_variable` does.
+    pub fn assign_variable(&mut self, name: &str, value: &str) -> Result<(), String> {
+        check_name(name)?;
+        let prefix_notation = self.translate_to_prefix(value.trim())?;
+        let equation = calculator::parse_to_vec(&prefix_notation)?;
+        if equation.is_empty() {
+            return Err(format!("No value given for {}.", name));
+        }
+        match value::calculate_with(&equation, &self.scope())? {
+            value::Value::Number(number) => {
+                self.lists.remove(name);
+                self.variables.insert(String::from(name), number);
+            }
+            value::Value::Vector(values) => {
+                self.variables.remove(name);
+                self.lists.insert(String::from(name), values);
+            }
+            other => return Err(format!("Only a number or a list of numbers can be stored, not {}.", other.describe())),
+        }
+        return Ok(());
+    }
 
-            let result_cal = calculator::calculate(&self.equation, &self.variables);
-            if result_cal.is_ok() {
-                let input_history = unwrap_html_textarea_element(document.get_element_by_id("inputHistory").unwrap());
-                if  input_history.selection_end().unwrap() == input_history.selection_start().unwrap() {
-                    input_history.set_scroll_top(input_history.scroll_height());
+    /// Renames a variable as `rename_variable` does.
+    pub fn rename(&mut self, name: &str, new_name: &str) -> Result<(), String> {
+        check_name(new_name)?;
+        if new_name != name && (self.variables.contains_key(new_name) || self.lists.contains_key(new_name)) {
+            return Err(format!("{} is already set.", new_name));
+        }
+        if let Some(number) = self.variables.remove(name) {
+            self.variables.insert(String::from(new_name), number);
+        } else if let Some(values) = self.lists.remove(name) {
+            self.lists.insert(String::from(new_name), values);
+        } else {
+            return Err(format!("{} is not set.", name));
+        }
+        for cell in self.equation.iter_mut() {
+            if let calculator::Cell::Variable(ref mut variable) = *cell {
+                if variable == name {
+                    *variable = String::from(new_name);
                 }
-                let mut ret = String::new();
-                ret.push_str(&infix_notation);
-                ret.push_str("\n");
-                input_history.set_value(&ret);
-
-                let output = unwrap_html_input_element(document.get_element_by_id("output").unwrap());
-                output.set_value(&calculator::convert_cell_vector_to_string(&result_cal.ok().unwrap()));
-                return Ok(());
-            } else {
-                let input_history = unwrap_html_textarea_element(document.get_element_by_id("inputHistory").unwrap());
-                if  input_history.selection_end().unwrap() == input_history.selection_start().unwrap() {
-                    input_history.set_scroll_top(input_history.scroll_height());
+            }
+        }
+        return Ok(());
+    }
+
+    /// Calculates the current equation again, after its variables changed. Returns None when
+    /// there is no equation.
+    pub fn recalculate(&self) -> Option<Result<String, String>> {
+        if self.equation.is_empty() {
+            return None;
+        }
+        let result = value::calculate_with(&self.equation, &self.scope());
+        return Some(result.map(|value| value::print_value_with(&value, &self.formats)).map_err(|e| format!("Error from calculator:{}", e)));
+    }
+
+    /// Replaces the session as `restore_session` does.
+    pub fn restore(&mut self, json: &str) -> Result<(), String> {
+        let saved = session::Saved::from_json(json)?;
+        for name in saved.variables.keys().chain(saved.lists.keys()) {
+            check_name(name).map_err(|e| format!("Invalid session: {}", e))?;
+        }
+        for (name, values) in saved.lists.iter() {
+            if values.is_empty() || values.iter().any(|value| !value.is_finite()) {
+                return Err(format!("Invalid session: list {} must hold at least one number, and only finite ones.", name));
+            }
+        }
+        self.variables = saved.variables.into_iter().collect();
+        self.lists = saved.lists.into_iter().collect();
+        self.session = session::History::with_entries(saved.inputs);
+        self.memory = saved.memory;
+        return Ok(());
+    }
+
+    /// Carries out a memory command with the last answer, which in stack mode is the top of the
+    /// stack. The answer is the value the command leaves; a recalled value also becomes the last
+    /// answer, and in stack mode goes on the stack.
+    fn evaluate_memory(&mut self, input: &str, command: memory::Command) -> Result<Entry, String> {
+        let answer = if self.stack_mode { self.stack.top() } else { self.answer };
+        let value = self.memory.apply(command, answer).map_err(|e| format!("Error from memory: {}", e))?;
+        if command.recalls() {
+            if self.stack_mode {
+                self.stack.execute(&value.to_string(), &self.variables).map_err(|e| format!("Error from stack: {}", e))?;
+            }
+            self.answer = Some(value);
+        }
+        return Ok(Entry { answer: value::print_value(&value::Value::Number(value)), history: format!("{}\n", input.trim()) });
+    }
+
+    /// Applies the input to the value stack; the answer is the top of the stack.
+    fn evaluate_stack(&mut self, entry: &str) -> Result<Entry, String> {
+        self.stack.execute(entry, &self.variables).map_err(|e| format!("Error from stack: {}", e))?;
+        let answer = match self.stack.top() {
+            Some(top) => calculator::convert_cell_vector_to_string(&[calculator::Cell::Value(top)]),
+            None => String::new(),
+        };
+        return Ok(Entry { answer, history: format!("{}\n", entry) });
+    }
+
+    /// Evaluates a call whose answer is not a single value: diff(x^2, x) gives the derivative in
+    /// infix notation and notes its prefix notation in the history, solve(x^2 = 2, x) gives the
+    /// roots and solve([x + y = 3, x - y = 1], [x, y]) gives the answer for each variable.
+    fn evaluate_symbolic(&mut self, input: &str, equation: &[calculator::Cell]) -> Result<Entry, String> {
+        let result = expression::from_cells(equation).and_then(|expression| match expression {
+            expression::Expression::Function(ref name, ref args) if name == "diff" => {
+                let (inner, variable) = derivative::get_call_arguments(args)?;
+                let answer = self.differentiate(inner, variable)?;
+                return Ok((answer.infix, format!("Prefix: {}\n", answer.prefix)));
+            }
+            expression::Expression::Function(ref name, ref args) if name == "solve" && is_list(&args[0]) => {
+                let answers = self.solve_system_call(args)?;
+                let answers = answers
+                    .iter()
+                    .map(|answer| format!("{} = {:.8}", answer.0, answer.1))
+                    .collect::<Vec<String>>();
+                return Ok((answers.join(", "), String::new()));
+            }
+            expression::Expression::Function(ref name, ref args) if name == "solve" => {
+                let roots = solve::solve_call(args, &self.variables).map_err(|e| e.message())?;
+                let roots = roots.iter().map(|root| format!("{:.8}", root)).collect::<Vec<String>>();
+                return Ok((roots.join(", "), String::new()));
+            }
+            _ => return Err(String::from("Expected a diff or solve call.")),
+        });
+        let (answer, note) = result.map_err(|e| format!("Error from calculator:{}", e))?;
+        return Ok(Entry { answer, history: format!("{}\n{}", input, note) });
+    }
+
+    fn differentiate(&self, expression: &expression::Expression, variable: &str) -> Result<Derivative, String> {
+        let answer = derivative::differentiate_simplified(expression, variable, &self.variables)?;
+        return Ok(Derivative {
+            infix: infix::print_infix(&answer),
+            prefix: prefix::print_prefix(&answer)
+        });
+    }
+
+    /// Stores the list when the input assigns one to a name, e.g. data = [1, 2, 3], returning the
+    /// list as printed. Returns None for any other input.
+    fn assign_list(&mut self, prefix_notation: &str) -> Option<Result<String, String>> {
+        let vec = calculator::parse_to_vec(prefix_notation).ok()?;
+        let name = match (vec.first(), vec.get(1), vec.get(2)) {
+            (Some(calculator::Cell::Operator(ref op)), Some(calculator::Cell::Variable(ref name)), Some(value)) if op == "=" => {
+                match *value {
+                    calculator::Cell::Function(ref list, _) if list == "list" => name.clone(),
+                    calculator::Cell::Variable(ref other) if self.lists.contains_key(other) => name.clone(),
+                    _ => return None,
                 }
-                let mut ret = String::new();
-                ret.push_str(&input_history.value());
-                ret.push_str("Error from calculator:");
-                ret.push_str(&result_cal.err().unwrap());
-                ret.push_str("\n");
-                input_history.set_value(&ret);
-                return Ok(());
             }
+            _ => return None,
+        };
+        if let Err(e) = check_name(&name) {
+            return Some(Err(e));
         }
+        let result = value::calculate_with(&vec[2..].to_vec(), &self.scope());
+        let result = result.and_then(|result| match result {
+            value::Value::Vector(values) => {
+                let answer = value::print_value(&value::Value::Vector(values.clone()));
+                self.variables.remove(&name);
+                self.lists.insert(name, values);
+                return Ok(answer);
+            }
+            other => return Err(format!("Only a list of numbers can be stored, not {}.", other.describe())),
+        });
+        return Some(result);
     }
+
+    /// Gives the variables, lists and current time an equation is evaluated with.
+    fn scope(&self) -> integrate::Variables<'_> {
+        let now = self.clock.unwrap_or_else(host_now) / 1000.0;
+        return integrate::Variables { numbers: &self.variables, lists: &self.lists, now: Some(now) };
+    }
+
+    fn solve_system_call(&mut self, args: &[expression::Expression]) -> Result<Vec<(String, f64)>, String> {
+        let answers = solve::solve_system_call(args, &self.variables).map_err(|e| e.message())?;
+        if self.store_solutions {
+            for answer in answers.iter() {
+                self.lists.remove(&answer.0);
+                self.variables.insert(answer.0.clone(), answer.1);
+            }
+        }
+        return Ok(answers);
+    }
+
+    /// Finds the first thing that keeps the input from being read, together with the span of
+    /// the input it concerns. Only infix input is read closely enough for that; in the other
+    /// notations, and in stack mode, only brackets are checked.
+    pub fn syntax_error(&self, input: &str) -> Option<token::CalcError> {
+        if self.stack_mode {
+            return None;
+        }
+        if let Err(e) = check_brackets(input) {
+            return Some(e);
+        }
+        if self.notation == Notation::Infix {
+            return prefix::parse_infix(input, &self.infix_options).err();
+        }
+        return None;
+    }
+
+    /// Translates input written in the current notation to the prefix notation the calculator reads.
+    fn translate_to_prefix(&self, input: &str) -> Result<String, String> {
+        check_brackets(input).map_err(|e| e.message)?;
+        // The infix parser reads every kind of bracket itself, since v[2] indexes a vector.
+        if self.notation == Notation::Infix {
+            return prefix::translate_infix_with(input, &self.infix_options);
+        }
+        let input = validate::normalize_brackets(input);
+        let input = input.as_str();
+        match self.notation {
+            Notation::Prefix => return Ok(String::from(input)),
+            _ => return postfix::translate_to_prefix(input),
+        }
+    }
+}
+
+/// Function that checks that a name can be given to a variable: a word that is not the name of
+/// a function, an operator, a constant or a memory command.
+#[allow(clippy::needless_return)]
+fn check_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let is_word = chars.next().is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|item| item.is_alphanumeric() || item == '_');
+    if !is_word {
+        return Err(format!("'{}' cannot be a variable name; use a letter followed by letters, digits or _.", name));
+    }
+    if calculator::is_function(name) || calculator::is_operator(name) || value::is_constant(name) || memory::is_command_name(name) {
+        return Err(format!("'{}' is a built-in name.", name));
+    }
+    return Ok(());
+}
+
+/// Function that checks the brackets of the input, suggesting how to close those left open.
+#[allow(clippy::needless_return)]
+fn check_brackets(input: &str) -> Result<(), token::CalcError> {
+    if let Err(e) = validate::check_brackets(input) {
+        let message = match validate::auto_close(input) {
+            Some(suggestion) => format!("{} Did you mean '{}'?", e.message, suggestion),
+            None => e.message,
+        };
+        return Err(token::CalcError::new(message, e.position, e.position + 1));
+    }
+    return Ok(());
+}
+
+#[allow(clippy::needless_return)]
+fn is_list(expression: &expression::Expression) -> bool {
+    match *expression {
+        expression::Expression::Function(ref name, _) => return name == "list",
+        _ => return false,
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = Date, js_name = now)]
+    fn date_now() -> f64;
+}
+
+/// Function that reads the host's clock in milliseconds since 1970-01-01 UTC.
+#[cfg(target_arch = "wasm32")]
+fn host_now() -> f64 {
+    return date_now();
+}
+
+#[allow(clippy::needless_return)]
+#[cfg(not(target_arch = "wasm32"))]
+fn host_now() -> f64 {
+    let since = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+    return since.map_or(0.0, |since| since.as_secs_f64() * 1000.0);
+}
+
+/// Function that shows the result of one line of input: the answer in the output box and the
+/// input, or the error, at the end of the history.
+#[allow(clippy::needless_return)]
+fn show_entry(output: &web_sys::HtmlInputElement, input_history: &web_sys::HtmlTextAreaElement, result: Result<Entry, String>) -> Result<(), JsValue> {
+    if input_history.selection_end()? == input_history.selection_start()? {
+        input_history.set_scroll_top(input_history.scroll_height());
+    }
+    let mut ret = String::new();
+    ret.push_str(&input_history.value());
+    match result {
+        Ok(entry) => {
+            ret.push_str(&entry.history);
+            output.set_value(&entry.answer);
+        }
+        Err(e) => {
+            ret.push_str(&e);
+            ret.push('\n');
+        }
+    }
+    input_history.set_value(&ret);
+    return Ok(());
+}
+
+/// Function that returns the page's document, or an error when there is no page.
+#[allow(clippy::needless_return)]
+fn get_document() -> Result<web_sys::Document, JsValue> {
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("No global `window` exists."))?;
+    return window.document().ok_or_else(|| JsValue::from_str("There is no document on the window."));
+}
+
+/// Function that finds an element by id and casts it to an HtmlTextAreaElement.
+#[allow(clippy::needless_return)]
+fn get_textarea_element(document: &web_sys::Document, id: &str) -> Result<web_sys::HtmlTextAreaElement, JsValue> {
+    let element = document.get_element_by_id(id).ok_or_else(|| missing_element(id))?;
+    return element
+        .dyn_into::<web_sys::HtmlTextAreaElement>()
+        .map_err(|_| JsValue::from_str(&format!("Element '{}' is not a text area.", id)));
+}
+
+/// Function that finds an element by id and casts it to an HtmlInputElement.
+#[allow(clippy::needless_return)]
+fn get_input_element(document: &web_sys::Document, id: &str) -> Result<web_sys::HtmlInputElement, JsValue> {
+    let element = document.get_element_by_id(id).ok_or_else(|| missing_element(id))?;
+    return element
+        .dyn_into::<web_sys::HtmlInputElement>()
+        .map_err(|_| JsValue::from_str(&format!("Element '{}' is not a text input.", id)));
 }
 
-/// Function that performs a specific dynamic cast from an Element to an HtmlTextAreaElement
-fn unwrap_html_textarea_element(element: web_sys::Element) -> web_sys::HtmlTextAreaElement {
-    return element.dyn_into::<web_sys::HtmlTextAreaElement>().unwrap();
+#[allow(clippy::needless_return)]
+fn missing_element(id: &str) -> JsValue {
+    return JsValue::from_str(&format!("No element with id '{}' exists.", id));
 }
 
-/// Function that performs a specific dynamic cast from an Element to an HtmlInputElement
-fn unwrap_html_input_element(element: web_sys::Element) -> web_sys::HtmlInputElement {
-    return element.dyn_into::<web_sys::HtmlInputElement>().unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let mut calc = Calc::new();
+        assert_eq!("5.00000000", calc.evaluate("x = 5").unwrap().answer);
+        assert_eq!("6.00000000", calc.evaluate("x + 1").unwrap().answer);
+        // Setting a variable recalculates the last equation.
+        assert_eq!("8.00000000", calc.evaluate("x = 7").unwrap().answer);
+        let entry = calc.evaluate("[1, 2] * x").unwrap();
+        assert_eq!("[1, 2] * x\n= [7, 14]\n", entry.history);
+        assert_eq!("2 * x", calc.evaluate("diff(x^2, x)").unwrap().answer);
+    }
+
+    #[test]
+    fn test_one_element_lists() {
+        let mut calc = Calc::new();
+        assert_eq!("[5]", calc.evaluate("[5]").unwrap().answer);
+        assert_eq!("[5]", calc.evaluate("x = [5]").unwrap().answer);
+        assert_eq!("[10]", calc.evaluate("2x").unwrap().answer);
+        assert_eq!("[[1, 2, 3]]", calc.evaluate("[[1, 2, 3]]").unwrap().answer);
+        assert_eq!("[14]", calc.evaluate("[[1, 2, 3]] * [1, 2, 3]").unwrap().answer);
+        assert!(calc.evaluate("det([[1, 2, 3]])").unwrap_err().contains("square"));
+    }
+
+    #[test]
+    fn test_date_formats() {
+        let mut calc = Calc::new();
+        assert_eq!("2026-10-19T09:30", calc.evaluate("2026-10-18T09:30 + 1 day").unwrap().answer);
+        calc.set_date_format(date::DateFormat::Long);
+        assert_eq!("19 October 2026 09:30", calc.evaluate("2026-10-18T09:30 + 1 day").unwrap().answer);
+        calc.set_date_format(date::DateFormat::Us);
+        assert_eq!(Some(Ok(String::from("10/19/2026 9:30 AM"))), calc.recalculate());
+        assert_eq!("1 day 2 hours", calc.evaluate("26 hours").unwrap().answer);
+        calc.set_duration_format(date::DurationFormat::Short);
+        assert_eq!("1d 2h", calc.evaluate("26 hours").unwrap().answer);
+        calc.set_duration_format(date::DurationFormat::Clock);
+        assert_eq!("26:00:00", calc.evaluate("26 hours").unwrap().answer);
+    }
+
+    #[test]
+    fn test_evaluate_notation_and_errors() {
+        let mut calc = Calc::new();
+        calc.set_notation(Notation::Postfix);
+        assert_eq!("5.00000000", calc.evaluate("2 3 +").unwrap().answer);
+        assert_eq!(Err(String::from("Error from calculator:Variable y does not have a defined value.")), calc.evaluate("y 1 +"));
+        assert!(calc.evaluate("2 +").unwrap_err().starts_with("Error parsing '2 +'"));
+        assert_eq!("338350.00000000", calc.evaluate("k 2 ^ k 1 100 sum").unwrap().answer);
+        calc.set_stack_mode(true);
+        calc.evaluate("4 5").unwrap();
+        assert_eq!("20.00000000", calc.evaluate("*").unwrap().answer);
+    }
+
+    #[test]
+    fn test_postfix_statistics() {
+        let mut calc = Calc::new();
+        calc.set_notation(Notation::Postfix);
+        calc.set_list("data", "2 4 4 4 5 5 7 9").unwrap();
+        let cases = [
+            ("5.00000000", "[ 2 4 4 4 5 5 7 9 ] mean"),
+            ("5.00000000", "data mean"),
+            ("4.50000000", "data median"),
+            ("4.00000000", "data mode"),
+            ("4.57142857", "data var"),
+            ("4.00000000", "data pvar"),
+            ("2.13808994", "data stdev"),
+            ("2.00000000", "data pstdev"),
+            ("7.60000000", "data 90 percentile"),
+            ("40.00000000", "data sum:1"),
+            ("6.00000000", "[ 1 2 3 ] sum"),
+            ("8.00000000", "data count"),
+            ("2.00000000", "data min:1"),
+            ("1.00000000", "[ 3 1 2 ] min"),
+            ("9.00000000", "data max:1"),
+            ("3.00000000", "1 3 max"),
+            ("2.00000000", "1 2 3 mean:3"),
+        ];
+        for (expected, input) in cases.iter() {
+            assert_eq!(*expected, calc.evaluate(input).unwrap().answer, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_store_solutions() {
+        let mut calc = Calc::new();
+        calc.set_list("x", "1 2 3").unwrap();
+        calc.set_store_solutions(true);
+        calc.evaluate("solve([x + y = 3, x - y = 1], [x, y])").unwrap();
+        assert_eq!(None, calc.list("x"));
+        let variables = calc.variables();
+        assert_eq!(
+            vec![("x", "2.00000000", "number"), ("y", "1.00000000", "number")],
+            variables.iter().map(|v| (v.name.as_str(), v.value.as_str(), v.kind)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let mut calc = Calc::new();
+        calc.evaluate("x = 2").unwrap();
+        calc.evaluate("x * 3").unwrap();
+        assert_eq!("12.00000000", calc.dry_run("x = 4").unwrap().answer);
+        assert!(calc.dry_run("x *").unwrap_err().starts_with("Error parsing 'x *'"));
+        assert_eq!("6.00000000", calc.dry_run("x * 3").unwrap().answer);
+        assert_eq!(vec![String::from("x = 2"), String::from("x * 3")], calc.inputs());
+        calc.set_stack_mode(true);
+        assert_eq!("3.00000000", calc.dry_run("1 2 +").unwrap().answer);
+        assert!(calc.stack().is_empty());
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let mut calc = Calc::new();
+        assert_eq!(None, calc.syntax_error("2 * (x + 1)"));
+        let error = calc.syntax_error("2 * (x + 1").unwrap();
+        assert_eq!((4, 5), (error.start, error.end));
+        assert_eq!("Unmatched '(' at position 5. Did you mean '2 * (x + 1)'?", error.message);
+        assert_eq!(Some((4, 5)), calc.syntax_error("2 + * 3").map(|e| (e.start, e.end)));
+        calc.set_notation(Notation::Postfix);
+        assert_eq!(None, calc.syntax_error("2 + * 3"));
+    }
+
+    #[test]
+    fn test_completions() {
+        let mut calc = Calc::new();
+        calc.evaluate("rate = 0.5").unwrap();
+        let completions = calc.completions("2 * ra + 1", 6);
+        assert_eq!(1, completions.len());
+        assert_eq!(("rate", "variable", "0.50000000"), (completions[0].name().as_str(), completions[0].kind().as_str(), completions[0].detail().as_str()));
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut calc = Calc::new();
+        calc.evaluate("r = 2").unwrap();
+        calc.evaluate("pi * r^2").unwrap();
+        calc.assign_variable("r", "1 / 2").unwrap();
+        assert_eq!(Some(Ok(String::from("0.78539816"))), calc.recalculate());
+        calc.assign_variable("data", "[1, 2, r]").unwrap();
+        let variables = calc.variables();
+        assert_eq!(
+            vec![("data", "[1, 2, 0.5]", "list"), ("r", "0.50000000", "number")],
+            variables.iter().map(|v| (v.name.as_str(), v.value.as_str(), v.kind)).collect::<Vec<_>>()
+        );
+        calc.rename("r", "radius").unwrap();
+        assert_eq!(Some(Ok(String::from("0.78539816"))), calc.recalculate());
+        assert!(calc.delete_variable("radius"));
+        assert!(!calc.delete_variable("radius"));
+        assert!(calc.recalculate().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_variable_errors() {
+        let mut calc = Calc::new();
+        assert!(calc.assign_variable("sin", "1").unwrap_err().contains("built-in"));
+        assert!(calc.assign_variable("2x", "1").unwrap_err().contains("cannot be a variable name"));
+        assert!(calc.assign_variable("x", "1 +").is_err());
+        assert!(calc.assign_variable("x", "").is_err());
+        assert!(calc.assign_variable("x", "1 < 2").unwrap_err().contains("Only a number"));
+        assert_eq!(Err(String::from("y is not set.")), calc.rename("y", "z"));
+        calc.assign_variable("x", "1").unwrap();
+        calc.assign_variable("y", "2").unwrap();
+        assert_eq!(Err(String::from("y is already set.")), calc.rename("x", "y"));
+    }
+
+    #[test]
+    fn test_constants_are_not_variables() {
+        let mut calc = Calc::new();
+        assert!(calc.variables().is_empty());
+        assert!(calc.assign_variable("pi", "3").unwrap_err().contains("built-in"));
+        assert!(calc.evaluate("e = 3").unwrap_err().contains("built-in"));
+        assert_eq!(Err(String::from("pi is not set.")), calc.rename("pi", "tau"));
+        assert!(!calc.delete_variable("e"));
+        calc.evaluate("x = 2").unwrap();
+        assert!(calc.rename("x", "pi").unwrap_err().contains("built-in"));
+        assert_eq!("6.28318531", calc.evaluate("2pi").unwrap().answer);
+        assert_eq!("7.38905610", calc.evaluate("e^x").unwrap().answer);
+        assert_eq!("[3.1415926536, 2.7182818285]", calc.evaluate("[pi, e]").unwrap().answer);
+        calc.set_stack_mode(true);
+        assert_eq!("3.14159265", calc.evaluate("pi").unwrap().answer);
+    }
+
+    #[test]
+    fn test_memory() {
+        let mut calc = Calc::new();
+        assert_eq!(Err(String::from("Error from memory: There is no answer to store yet.")), calc.evaluate("m+"));
+        calc.evaluate("2 * 3").unwrap();
+        assert_eq!("6.00000000", calc.evaluate("m+").unwrap().answer);
+        calc.evaluate("1 + 1").unwrap();
+        assert_eq!("4.00000000", calc.evaluate("M-").unwrap().answer);
+        assert_eq!("2.00000000", calc.evaluate("sto 3").unwrap().answer);
+        assert_eq!("4.00000000", calc.evaluate("mr").unwrap().answer);
+        assert_eq!("8.00000000", calc.evaluate("m+").unwrap().answer);
+        assert_eq!("0.00000000", calc.evaluate("mc").unwrap().answer);
+        assert_eq!("2.00000000", calc.evaluate("rcl 3").unwrap().answer);
+        assert_eq!(Some(2.0), calc.register(3));
+        assert!(calc.evaluate("rcl 4").unwrap_err().contains("Register 4 is empty."));
+        assert!(calc.evaluate("sto 12").unwrap_err().starts_with("Error from memory: Registers are numbered"));
+        calc.set_stack_mode(true);
+        calc.evaluate("5").unwrap();
+        calc.evaluate("m+").unwrap();
+        calc.evaluate("rcl 3").unwrap();
+        assert_eq!(vec![5.0, 2.0], calc.stack());
+        assert_eq!(5.0, calc.memory());
+    }
+
+    #[test]
+    fn test_memory_names_reserved() {
+        let mut calc = Calc::new();
+        calc.evaluate("4").unwrap();
+        calc.evaluate("m+").unwrap();
+        assert!(calc.evaluate("mc = 3").unwrap_err().contains("'mc' is a built-in name."));
+        assert!(calc.evaluate("Sto = [1, 2]").unwrap_err().contains("built-in"));
+        assert!(calc.assign_variable("rcl", "1").unwrap_err().contains("built-in"));
+        calc.evaluate("x = 1").unwrap();
+        assert!(calc.rename("x", "mr").unwrap_err().contains("built-in"));
+        assert_eq!("0.00000000", calc.evaluate("mc").unwrap().answer);
+        assert_eq!(0.0, calc.memory());
+        assert!(!calc.variables().iter().any(|variable| variable.name == "mc"));
+    }
+
+    #[test]
+    fn test_session() {
+        let mut calc = Calc::new();
+        calc.evaluate("x = 4").unwrap();
+        calc.evaluate("sto 1").unwrap();
+        calc.assign_variable("data", "[1, 2]").unwrap();
+        let saved = calc.save_session();
+        let mut restored = Calc::new();
+        restored.restore(&saved).unwrap();
+        assert_eq!(calc.variables(), restored.variables());
+        assert_eq!(vec![String::from("x = 4"), String::from("sto 1")], restored.inputs());
+        assert_eq!(Some(4.0), restored.register(1));
+        assert!(restored.restore("{\"inputs\": 3}").is_err());
+        assert!(restored.restore("{\"variables\": {\"1bad x\": 2}}").unwrap_err().contains("cannot be a variable name"));
+        assert!(restored.restore("{\"lists\": {\"sin\": [3]}}").unwrap_err().contains("built-in"));
+        assert!(restored.restore("{\"lists\": {\"d\": []}}").unwrap_err().contains("list d must hold"));
+        assert!(restored.list("d").is_none());
+        assert_eq!(Some(4.0), restored.register(1));
+    }
+
+    #[test]
+    fn test_session_not_finite() {
+        let mut calc = Calc::new();
+        calc.evaluate("0 / 0").unwrap();
+        calc.evaluate("m+").unwrap();
+        calc.evaluate("sto 2").unwrap();
+        calc.evaluate("1 / 0").unwrap();
+        calc.evaluate("sto 3").unwrap();
+        calc.assign_variable("data", "[1 / 0, 2]").unwrap();
+        calc.assign_variable("good", "[1, 2]").unwrap();
+        let mut restored = Calc::new();
+        restored.restore(&calc.save_session()).unwrap();
+        assert_eq!(0.0, restored.memory());
+        assert_eq!(None, restored.register(2));
+        assert_eq!(None, restored.register(3));
+        assert_eq!(None, restored.list("data"));
+        assert_eq!(Some(vec![1.0, 2.0]), restored.list("good"));
+    }
+
+    #[test]
+    fn test_input_history() {
+        let mut calc = Calc::new();
+        calc.evaluate("x = 2").unwrap();
+        calc.evaluate("x +").unwrap_err();
+        assert_eq!(vec![String::from("x = 2"), String::from("x +")], calc.inputs());
+        assert_eq!(Some(String::from("x +")), calc.previous_input("x"));
+        assert_eq!(Some(String::from("x = 2")), calc.previous_input("x +"));
+        assert_eq!(Some(String::from("x +")), calc.next_input());
+        assert_eq!(Some(String::from("x")), calc.next_input());
+    }
 }
@@ -0,0 +1,206 @@
+#![allow(clippy::needless_return)]
+
+/// Enum that describes why a system of linear equations has no single solution.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinearError {
+    /// The equations contradict each other, so there is no solution.
+    Inconsistent,
+    /// The listed unknowns (by column) can take any value, so there are infinitely many solutions.
+    Underdetermined { free: Vec<usize> },
+}
+
+/// Function that solves the linear system A x = b by Gaussian elimination with partial pivoting.
+/// `coefficients` holds one row of A per equation and `constants` holds b. Any number of equations
+/// is accepted as long as they pin down every unknown.
+/// # Example
+/// [[2, 1], [1, -1]] x = [5, 1] -> [2, 1]
+/// ...
+pub fn solve_linear(coefficients: &[Vec<f64>], constants: &[f64]) -> Result<Vec<f64>, LinearError> {
+    let rows = coefficients.len();
+    let columns = coefficients.first().map_or(0, |row| row.len());
+    let mut matrix = Vec::<Vec<f64>>::new();
+    for (row, constant) in coefficients.iter().zip(constants.iter()) {
+        let mut augmented = row.clone();
+        augmented.push(*constant);
+        matrix.push(augmented);
+    }
+    let scale = coefficients
+        .iter()
+        .flat_map(|row| row.iter())
+        .fold(0.0, |largest: f64, val| largest.max(val.abs()));
+    let epsilon = 1e-12 * scale.max(1.0);
+
+    let mut pivots = Vec::<usize>::new();
+    let mut free = Vec::<usize>::new();
+    for column in 0..columns {
+        let row = pivots.len();
+        if row == rows {
+            free.push(column);
+            continue;
+        }
+        let pivot = (row..rows)
+            .max_by(|a, b| matrix[*a][column].abs().partial_cmp(&matrix[*b][column].abs()).unwrap())
+            .unwrap();
+        if matrix[pivot][column].abs() <= epsilon {
+            free.push(column);
+            continue;
+        }
+        matrix.swap(row, pivot);
+        let pivot_row = matrix[row].clone();
+        for below in matrix.iter_mut().skip(row + 1) {
+            let factor = below[column] / pivot_row[column];
+            for (target, source) in below.iter_mut().zip(pivot_row.iter()).skip(column) {
+                *target -= factor * source;
+            }
+        }
+        pivots.push(column);
+    }
+
+    // Rows left without a pivot read 0 = constant.
+    for row in matrix.iter().skip(pivots.len()) {
+        if row[columns].abs() > epsilon * columns.max(1) as f64 {
+            return Err(LinearError::Inconsistent);
+        }
+    }
+    if !free.is_empty() {
+        return Err(LinearError::Underdetermined { free });
+    }
+
+    let mut ret = vec![0.0; columns];
+    for (row, column) in pivots.iter().enumerate().rev() {
+        let mut sum = matrix[row][columns];
+        for index in column + 1..columns {
+            sum -= matrix[row][index] * ret[index];
+        }
+        ret[*column] = sum / matrix[row][*column];
+    }
+    return Ok(ret);
+}
+
+/// Function that finds the determinant of a square matrix by elimination with partial pivoting.
+pub fn determinant(matrix: &[Vec<f64>]) -> f64 {
+    let size = matrix.len();
+    let mut matrix = matrix.to_vec();
+    let mut ret = 1.0;
+    for column in 0..size {
+        let pivot = (column..size)
+            .max_by(|a, b| matrix[*a][column].abs().partial_cmp(&matrix[*b][column].abs()).unwrap())
+            .unwrap();
+        if matrix[pivot][column] == 0.0 {
+            return 0.0;
+        }
+        if pivot != column {
+            matrix.swap(column, pivot);
+            ret = -ret;
+        }
+        ret *= matrix[column][column];
+        let pivot_row = matrix[column].clone();
+        for below in matrix.iter_mut().skip(column + 1) {
+            let factor = below[column] / pivot_row[column];
+            for (target, source) in below.iter_mut().zip(pivot_row.iter()).skip(column) {
+                *target -= factor * source;
+            }
+        }
+    }
+    return ret;
+}
+
+/// Function that inverts a square matrix by Gauss-Jordan elimination with partial pivoting.
+/// Returns None when the matrix is singular.
+pub fn inverse(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let size = matrix.len();
+    let scale = matrix
+        .iter()
+        .flat_map(|row| row.iter())
+        .fold(0.0, |largest: f64, val| largest.max(val.abs()));
+    let epsilon = 1e-12 * scale.max(1.0);
+    // Work on [matrix | identity] until the left half is the identity.
+    let mut augmented = Vec::<Vec<f64>>::new();
+    for (index, row) in matrix.iter().enumerate() {
+        let mut augmented_row = row.clone();
+        augmented_row.extend((0..size).map(|column| if column == index { 1.0 } else { 0.0 }));
+        augmented.push(augmented_row);
+    }
+    for column in 0..size {
+        let pivot = (column..size)
+            .max_by(|a, b| augmented[*a][column].abs().partial_cmp(&augmented[*b][column].abs()).unwrap())
+            .unwrap();
+        if augmented[pivot][column].abs() <= epsilon {
+            return None;
+        }
+        augmented.swap(column, pivot);
+        let divisor = augmented[column][column];
+        for val in augmented[column].iter_mut() {
+            *val /= divisor;
+        }
+        let pivot_row = augmented[column].clone();
+        for (index, row) in augmented.iter_mut().enumerate() {
+            if index == column {
+                continue;
+            }
+            let factor = row[column];
+            for (target, source) in row.iter_mut().zip(pivot_row.iter()) {
+                *target -= factor * source;
+            }
+        }
+    }
+    return Some(augmented.into_iter().map(|row| row[size..].to_vec()).collect());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: &[f64], actual: &[f64]) {
+        assert_eq!(expected.len(), actual.len());
+        for (expected, actual) in expected.iter().zip(actual.iter()) {
+            assert!((expected - actual).abs() < 1e-9, "{:?}", actual);
+        }
+    }
+
+    #[test]
+    fn test_two_by_two() {
+        let result = solve_linear(&[vec![2.0, 1.0], vec![1.0, -1.0]], &[5.0, 1.0]).unwrap();
+        assert_close(&[2.0, 1.0], &result);
+    }
+
+    #[test]
+    fn test_needs_pivoting() {
+        let coefficients = [vec![0.0, 1.0, 1.0], vec![1.0, 0.0, 1.0], vec![1.0, 1.0, 0.0]];
+        let result = solve_linear(&coefficients, &[5.0, 4.0, 3.0]).unwrap();
+        assert_close(&[1.0, 2.0, 3.0], &result);
+        let result = solve_linear(&[vec![1e-20, 1.0], vec![1.0, 1.0]], &[1.0, 2.0]).unwrap();
+        assert_close(&[1.0, 1.0], &result);
+    }
+
+    #[test]
+    fn test_extra_consistent_equation() {
+        let coefficients = [vec![1.0, 1.0], vec![1.0, -1.0], vec![2.0, 0.0]];
+        let result = solve_linear(&coefficients, &[3.0, 1.0, 4.0]).unwrap();
+        assert_close(&[2.0, 1.0], &result);
+    }
+
+    #[test]
+    fn test_singular() {
+        let coefficients = [vec![1.0, 2.0], vec![2.0, 4.0]];
+        assert_eq!(Err(LinearError::Inconsistent), solve_linear(&coefficients, &[3.0, 7.0]));
+        assert_eq!(Err(LinearError::Underdetermined { free: vec![1] }), solve_linear(&coefficients, &[3.0, 6.0]));
+        assert_eq!(Err(LinearError::Underdetermined { free: vec![1] }), solve_linear(&[vec![1.0, 1.0]], &[2.0]));
+    }
+
+    #[test]
+    fn test_determinant() {
+        assert_eq!(-2.0, determinant(&[vec![1.0, 2.0], vec![3.0, 4.0]]));
+        assert_eq!(0.0, determinant(&[vec![1.0, 2.0], vec![2.0, 4.0]]));
+        let coefficients = [vec![0.0, 1.0, 1.0], vec![1.0, 0.0, 1.0], vec![1.0, 1.0, 0.0]];
+        assert!((2.0 - determinant(&coefficients)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let result = inverse(&[vec![4.0, 7.0], vec![2.0, 6.0]]).unwrap();
+        assert_close(&[0.6, -0.7], &result[0]);
+        assert_close(&[-0.2, 0.4], &result[1]);
+        assert_eq!(None, inverse(&[vec![1.0, 2.0], vec![2.0, 4.0]]));
+    }
+}
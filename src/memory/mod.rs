@@ -0,0 +1,146 @@
+#![allow(clippy::needless_return)]
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Number of numbered registers; they are numbered from 0.
+pub const REGISTERS: usize = 10;
+
+/// Struct that holds the memory of a desk calculator: the register M+, M-, MR and MC work on,
+/// and numbered registers for STO and RCL. It is kept apart from the variables.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Memory {
+    pub register: f64,
+    pub registers: BTreeMap<usize, f64>,
+}
+
+/// Enum that defines the memory commands:
+/// * Add [m+, adds the answer to the memory]
+/// * Subtract [m-, subtracts the answer from the memory]
+/// * Recall [mr, gives the memory]
+/// * Clear [mc, sets the memory to 0]
+/// * Store [sto n, copies the answer to register n]
+/// * RecallRegister [rcl n, gives register n]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Add,
+    Subtract,
+    Recall,
+    Clear,
+    Store(usize),
+    RecallRegister(usize),
+}
+
+impl Command {
+    /// Function that reports whether the command gives a value to use, rather than storing one.
+    pub fn recalls(&self) -> bool {
+        match *self {
+            Command::Recall | Command::RecallRegister(_) => return true,
+            _ => return false,
+        }
+    }
+}
+
+/// Function that reports whether a name is taken by a memory command, ignoring case, so that it
+/// cannot be a variable.
+pub fn is_command_name(name: &str) -> bool {
+    match name.to_lowercase().as_str() {
+        "mc" | "mr" | "sto" | "rcl" => return true,
+        _ => return false,
+    }
+}
+
+/// Function that reads a memory command, ignoring case. Returns None when the input is not one,
+/// and an error when it is one with a bad register number.
+/// # Example
+/// M+ -> Add
+/// sto 3 -> Store(3)
+/// ...
+pub fn parse_command(input: &str) -> Option<Result<Command, String>> {
+    let input = input.trim().to_lowercase();
+    let mut words = input.split_whitespace();
+    let command = words.next()?;
+    let argument = words.next();
+    if words.next().is_some() {
+        return None;
+    }
+    match (command, argument) {
+        ("m+", None) => return Some(Ok(Command::Add)),
+        ("m-", None) => return Some(Ok(Command::Subtract)),
+        ("mr", None) => return Some(Ok(Command::Recall)),
+        ("mc", None) => return Some(Ok(Command::Clear)),
+        ("sto", Some(number)) | ("rcl", Some(number)) => {
+            let register = match number.parse::<usize>() {
+                Ok(register) if register < REGISTERS => register,
+                _ => return Some(Err(format!("Registers are numbered 0 to {}, not {}.", REGISTERS - 1, number))),
+            };
+            if command == "sto" {
+                return Some(Ok(Command::Store(register)));
+            }
+            return Some(Ok(Command::RecallRegister(register)));
+        }
+        ("sto", None) | ("rcl", None) => return Some(Err(format!("{} needs a register number, e.g. {} 1.", command, command))),
+        _ => return None,
+    }
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        return Memory::default();
+    }
+
+    /// Function that carries out a command, where `answer` is the answer shown last. Returns the
+    /// value the command leaves: the memory after m+, m- and mc, the value stored by sto and the
+    /// value recalled by mr and rcl.
+    pub fn apply(&mut self, command: Command, answer: Option<f64>) -> Result<f64, String> {
+        let answer = || answer.ok_or_else(|| String::from("There is no answer to store yet."));
+        match command {
+            Command::Add => self.register += answer()?,
+            Command::Subtract => self.register -= answer()?,
+            Command::Recall => {}
+            Command::Clear => self.register = 0.0,
+            Command::Store(register) => {
+                let answer = answer()?;
+                self.registers.insert(register, answer);
+                return Ok(answer);
+            }
+            Command::RecallRegister(register) => {
+                return self.registers.get(&register).cloned().ok_or_else(|| format!("Register {} is empty.", register));
+            }
+        }
+        return Ok(self.register);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(Some(Ok(Command::Add)), parse_command("M+"));
+        assert_eq!(Some(Ok(Command::Subtract)), parse_command(" m- "));
+        assert_eq!(Some(Ok(Command::Store(3))), parse_command("sto 3"));
+        assert_eq!(Some(Ok(Command::RecallRegister(0))), parse_command("RCL 0"));
+        assert!(parse_command("sto 10").unwrap().is_err());
+        assert!(parse_command("rcl").unwrap().is_err());
+        assert_eq!(None, parse_command("mr + 1"));
+        assert_eq!(None, parse_command("m"));
+        assert_eq!(None, parse_command("stop 3"));
+        assert!(is_command_name("MR"));
+        assert!(!is_command_name("m"));
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut memory = Memory::new();
+        assert!(memory.apply(Command::Add, None).is_err());
+        assert_eq!(Ok(5.0), memory.apply(Command::Add, Some(5.0)));
+        assert_eq!(Ok(3.0), memory.apply(Command::Subtract, Some(2.0)));
+        assert_eq!(Ok(3.0), memory.apply(Command::Recall, None));
+        assert_eq!(Ok(0.0), memory.apply(Command::Clear, Some(9.0)));
+        assert_eq!(Err(String::from("Register 3 is empty.")), memory.apply(Command::RecallRegister(3), None));
+        assert_eq!(Ok(7.0), memory.apply(Command::Store(3), Some(7.0)));
+        assert_eq!(Ok(7.0), memory.apply(Command::RecallRegister(3), None));
+    }
+}
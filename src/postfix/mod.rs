@@ -0,0 +1,350 @@
+#![allow(clippy::needless_return)]
+
+use calculator;
+use expression;
+use expression::Expression;
+use prefix;
+use statistics;
+
+/// Function that translates a prefix notated equation to a postfix (reverse Polish) notated equation.
+/// # Example
+/// + a b -> a b +
+/// ...
+#[allow(clippy::doc_lazy_continuation)]
+pub fn translate_prefix(prefix_notation: &str) -> Result<String, String> {
+    let expression = expression::from_prefix(prefix_notation)?;
+    return Ok(print_postfix(&expression));
+}
+
+/// Function that translates an infix notated equation to a postfix (reverse Polish) notated equation.
+/// # Example
+/// (a + b) * c -> a b + c *
+/// ...
+pub fn translate_infix(infix_notation: &str) -> Result<String, String> {
+    return translate_prefix(&prefix::translate_infix(infix_notation)?);
+}
+
+/// Function that builds an equation tree from a postfix (reverse Polish) notated equation.
+/// A list is written between brackets, e.g. [ 1 2 3 ]. A function that takes any number of
+/// arguments may be followed by how many it is given, e.g. 1 2 3 mean:3.
+pub fn from_postfix(postfix_notation: &str) -> Result<Expression, String> {
+    let mut stack = Vec::<Expression>::new();
+    let mut lists = Vec::<usize>::new();
+    for item in postfix_notation.split_whitespace() {
+        match item {
+            "[" | "(" => {
+                lists.push(stack.len());
+            }
+            "]" | ")" => match lists.pop() {
+                Some(start) if start < stack.len() => {
+                    let items = stack.split_off(start);
+                    stack.push(Expression::Function(String::from("list"), items));
+                }
+                Some(_) => return Err(String::from("Empty list.")),
+                None => return Err(String::from("Unexpected closing bracket.")),
+            },
+            item if calculator::is_operator(item) => {
+                if stack.len() < 2 {
+                    return Err(format!("Not enough values to apply to Operator {}.", item));
+                }
+                let right = stack.pop().unwrap();
+                let left = stack.pop().unwrap();
+                stack.push(Expression::Operator(String::from(item), vec![left, right]));
+            }
+            item if calculator::is_function(function_name(item)) => {
+                let name = function_name(item);
+                let count = match item.get(name.len() + 1..) {
+                    Some(count) => match count.parse::<usize>() {
+                        Ok(count) => count,
+                        Err(_) => return Err(format!("Write the number of arguments of {} as a whole number, e.g. {}:3.", name, name)),
+                    },
+                    None => default_count(name, stack.last()),
+                };
+                calculator::check_arity(name, count)?;
+                if stack.len() < count {
+                    return Err(format!("Not enough values to apply to Function {}.", name));
+                }
+                let args = stack.split_off(stack.len() - count);
+                stack.push(Expression::Function(String::from(name), args));
+            }
+            _ => match item.parse::<f64>() {
+                Ok(val) => stack.push(Expression::Value(val)),
+                Err(_) => stack.push(Expression::Variable(String::from(item))),
+            },
+        }
+    }
+    if !lists.is_empty() {
+        return Err(String::from("Missing closing bracket."));
+    }
+    match stack.len() {
+        0 => return Err(String::from("Nothing to translate.")),
+        1 => return Ok(stack.pop().unwrap()),
+        _ => return Err(String::from("Did not use all values!")),
+    }
+}
+
+/// Private function that returns the name of a function written with a number of arguments,
+/// e.g. mean of mean:3.
+fn function_name(item: &str) -> &str {
+    return item.split(':').next().unwrap();
+}
+
+/// Private function that gives how many operands a function written without a number of
+/// arguments takes in postfix, where `last` is the operand just before it:
+/// * Its fixed number, if it has one [e.g. x sin]
+/// * One, when that operand is a list [e.g. [ 1 2 3 ] mean or [ 1 2 3 ] sum]
+/// * Four for sum, as for integrate and product [e.g. k 2 ^ k 1 100 sum]
+/// * The fewest a statistics function takes [e.g. data mean or data 90 percentile]
+/// * Two for the rest, like the operators [e.g. a b max]
+fn default_count(name: &str, last: Option<&Expression>) -> usize {
+    let (least, most) = calculator::get_function_arity(name);
+    if let Some(most) = most {
+        return most;
+    }
+    if least == 1 && matches!(last, Some(Expression::Function(list, _)) if list == "list") {
+        return 1;
+    }
+    match name {
+        "sum" => return 4,
+        "min" | "max" => return 2,
+        name if statistics::is_statistic(name) => return least,
+        _ => return least.max(2),
+    }
+}
+
+/// Function that translates a postfix notated equation to a prefix notated equation.
+/// # Example
+/// a b + c * -> * + a b c
+/// ...
+pub fn translate_to_prefix(postfix_notation: &str) -> Result<String, String> {
+    let expression = from_postfix(postfix_notation)?;
+    return Ok(prefix::print_prefix(&expression));
+}
+
+/// Function that prints an equation tree in postfix (reverse Polish) notation.
+pub fn print_postfix(expression: &Expression) -> String {
+    match *expression {
+        Expression::Value(ref val) => {
+            return format!("{}", val);
+        }
+        Expression::Variable(ref val) => {
+            return val.clone();
+        }
+        Expression::Operator(ref op, ref operands) if operands.len() == 1 => {
+            // Postfix operators are always binary, so negate and invert against the identity.
+            let identity = if op == "/" { "1" } else { "0" };
+            return format!("{} {} {}", identity, print_postfix(&operands[0]), op);
+        }
+        Expression::Function(ref name, ref args) if name == "list" => {
+            // Lists keep their brackets so that the number of items is known.
+            let args = args.iter().map(print_postfix).collect::<Vec<String>>();
+            return format!("[ {} ]", args.join(" "));
+        }
+        Expression::Function(ref name, ref args) => {
+            // The number of arguments is written when the function would not take as many
+            // without it, e.g. 1 2 3 mean:3.
+            let mut ret = String::new();
+            for arg in args.iter() {
+                ret.push_str(&print_postfix(arg));
+                ret.push(' ');
+            }
+            ret.push_str(name);
+            if default_count(name, args.last()) != args.len() {
+                ret.push_str(&format!(":{}", args.len()));
+            }
+            return ret;
+        }
+        Expression::Operator(ref name, ref args) => {
+            // Postfix operators are binary, so variadic ones are folded from the left, which is
+            // how they apply their arguments.
+            let mut ret = String::new();
+            for (index, arg) in args.iter().enumerate() {
+                if index > 0 {
+                    ret.push(' ');
+                }
+                ret.push_str(&print_postfix(arg));
+                if index > 0 {
+                    ret.push(' ');
+                    ret.push_str(name);
+                }
+            }
+            return ret;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Expression {
+        return Expression::Variable(String::from(name));
+    }
+
+    fn op(name: &str, left: Expression, right: Expression) -> Expression {
+        return Expression::Operator(String::from(name), vec![left, right]);
+    }
+
+    #[test]
+    fn test_simple_add() {
+        let input = "+ 2 2";
+        let result = "2 2 +";
+        assert_eq!(result, translate_prefix(input).unwrap());
+    }
+
+    #[test]
+    fn test_simple_subtract() {
+        let input = "- 2 2";
+        let result = "2 2 -";
+        assert_eq!(result, translate_prefix(input).unwrap());
+    }
+
+    #[test]
+    fn test_simple_multiply() {
+        let input = "* 2 2";
+        let result = "2 2 *";
+        assert_eq!(result, translate_prefix(input).unwrap());
+    }
+
+    #[test]
+    fn test_simple_divide() {
+        let input = "/ 2 2";
+        let result = "2 2 /";
+        assert_eq!(result, translate_prefix(input).unwrap());
+    }
+
+    #[test]
+    fn test_complex_one() {
+        let input = "/ ( + a * b c ) ( - d / f g )";
+        let result = "a b c * + d f g / - /";
+        assert_eq!(result, translate_prefix(input).unwrap());
+    }
+
+    #[test]
+    fn test_from_infix() {
+        let input = "(j + k) * (a - b / c)";
+        let result = "j k + a b c / - *";
+        assert_eq!(result, translate_infix(input).unwrap());
+    }
+
+    #[test]
+    fn test_postfix_equals() {
+        let input = "a = -3";
+        let result = "a -3 =";
+        assert_eq!(result, translate_infix(input).unwrap());
+    }
+
+    #[test]
+    fn test_variadic() {
+        assert_eq!("1 2 + 3 + 4 +", translate_prefix("(+ 1 2 3 4)").unwrap());
+        assert_eq!("0 a b + -", translate_prefix("(- (+ a b))").unwrap());
+    }
+
+    #[test]
+    fn test_lists() {
+        assert_eq!("[ 2 x * y + 5 = x y - 1 = ]", translate_infix("[2x + y = 5, x - y = 1]").unwrap());
+        assert_eq!("[ x y ]", translate_prefix("( list x y )").unwrap());
+        assert_eq!("a b c max:3", translate_prefix("(max a b c)").unwrap());
+        assert_eq!("x sin 2 /", translate_prefix("/ (sin x) 2").unwrap());
+    }
+
+    #[test]
+    fn test_from_postfix_function() {
+        let result = Expression::Function(String::from("sin"), vec![op("+", var("a"), var("b"))]);
+        assert_eq!(Ok(result), from_postfix("a b + sin"));
+    }
+
+    #[test]
+    fn test_from_postfix_list() {
+        let result = Expression::Function(String::from("list"), vec![op("+", var("a"), var("b")), var("c")]);
+        assert_eq!(Ok(result), from_postfix("[ a b + c ]"));
+        assert_eq!(Err(String::from("Missing closing bracket.")), from_postfix("[ a b"));
+        assert_eq!(Err(String::from("Empty list.")), from_postfix("[ ]"));
+    }
+
+    #[test]
+    fn test_from_postfix() {
+        let result = op("*", op("+", var("a"), var("b")), Expression::Value(3.0));
+        assert_eq!(Ok(result), from_postfix("a b + 3 *"));
+    }
+
+    #[test]
+    fn test_from_postfix_missing_operand() {
+        let result = Err(String::from("Not enough values to apply to Operator *."));
+        assert_eq!(result, from_postfix("a b + *"));
+    }
+
+    #[test]
+    fn test_from_postfix_extra_operand() {
+        let result = Err(String::from("Did not use all values!"));
+        assert_eq!(result, from_postfix("a b c +"));
+    }
+
+    #[test]
+    fn test_translate_to_prefix() {
+        assert_eq!("+ 2 2", translate_to_prefix("2 2 +").unwrap());
+        assert_eq!("* + a b c", translate_to_prefix("a b + c *").unwrap());
+        assert_eq!("- a - b c", translate_to_prefix("a b c - -").unwrap());
+        assert_eq!("= a -3", translate_to_prefix("a -3 =").unwrap());
+    }
+
+    #[test]
+    fn test_sum() {
+        assert_eq!("( sum ^ k 2 k 1 100 )", translate_to_prefix("k 2 ^ k 1 100 sum").unwrap());
+        assert_eq!("( integrate * x x x 0 1 )", translate_to_prefix("x x * x 0 1 integrate").unwrap());
+    }
+
+    #[test]
+    fn test_argument_counts() {
+        assert_eq!("( mean ( list 1 2 3 ) )", translate_to_prefix("[ 1 2 3 ] mean").unwrap());
+        assert_eq!("( mean data )", translate_to_prefix("data mean").unwrap());
+        assert_eq!("( mean 1 2 3 )", translate_to_prefix("1 2 3 mean:3").unwrap());
+        assert_eq!("( sum ( list 1 2 ) )", translate_to_prefix("[ 1 2 ] sum").unwrap());
+        assert_eq!("( sum data )", translate_to_prefix("data sum:1").unwrap());
+        assert_eq!("( percentile data 90 )", translate_to_prefix("data 90 percentile").unwrap());
+        assert_eq!("( max a b )", translate_to_prefix("a b max").unwrap());
+        assert_eq!("( max a b c )", translate_to_prefix("a b c max:3").unwrap());
+        assert!(translate_to_prefix("a b c max:x").unwrap_err().contains("whole number"));
+        assert!(translate_to_prefix("a sin:2").is_err());
+        assert!(translate_to_prefix("a b mean:3").unwrap_err().starts_with("Not enough values"));
+    }
+
+    #[test]
+    fn test_print_argument_counts() {
+        assert_eq!("[ 1 2 3 ] mean", translate_infix("mean([1, 2, 3])").unwrap());
+        assert_eq!("1 2 3 mean:3", translate_infix("mean(1, 2, 3)").unwrap());
+        assert_eq!("data mean", translate_infix("mean(data)").unwrap());
+        assert_eq!("data sum:1", translate_infix("sum(data)").unwrap());
+        assert_eq!("k 2 ^ k 1 100 sum", translate_infix("sum(k^2, k, 1, 100)").unwrap());
+        assert_eq!("data 90 percentile", translate_infix("percentile(data, 90)").unwrap());
+        assert_eq!("a b max", translate_infix("max(a, b)").unwrap());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let inputs = [
+            "(a + b) * c",
+            "mean([1, 2, 3])",
+            "mean(1, 2, 3)",
+            "median(data)",
+            "sum(data)",
+            "sum(1, 2)",
+            "sum(k^2, k, 1, 100)",
+            "percentile(1, 2, 3, 50)",
+            "max(a, b, c)",
+            "max([a, b], [c, d])",
+            "min(a, b)",
+            "stdev(data) / mean(data)",
+        ];
+        for input in inputs.iter() {
+            let expression = expression::from_prefix(&prefix::translate_infix(input).unwrap()).unwrap();
+            assert_eq!(Ok(expression.clone()), from_postfix(&print_postfix(&expression)), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_translate_to_prefix_error() {
+        assert!(translate_to_prefix("a +").is_err());
+        assert!(translate_to_prefix("a b").is_err());
+    }
+}
@@ -1,233 +1,585 @@
-use unicode_segmentation::UnicodeSegmentation;
-use regex::Regex;
+#![allow(clippy::needless_return)]
 
-/// Function that translates a infix notated equation to an prefix notated equation.
+use calculator;
+use expression::Expression;
+use token;
+use token::{CalcError, Token, TokenKind};
+
+/// Struct that holds the settings of the infix parser.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InfixOptions {
+    /// Whether juxtaposed operands, e.g. 2pi or (a + b)(a - b), are multiplied.
+    pub implicit_multiplication: bool,
+    /// Whether implicit multiplication binds tighter than '*' and '/', making 1/2x mean 1/(2x)
+    /// rather than (1/2)x.
+    pub implicit_binds_tighter: bool,
+}
+
+impl Default for InfixOptions {
+    fn default() -> InfixOptions {
+        return InfixOptions {
+            implicit_multiplication: true,
+            implicit_binds_tighter: false,
+        };
+    }
+}
+
+/// Function that translates a infix notated equation to an prefix notated equation, using the
+/// default parser settings.
 /// # Example
 /// a + b -> + a b
-///
-/// TODO: capture and fix negative signifier
-///
-/// a + -b -> b - + a -> -b + a -> -b a + -> + a -b
+/// (a + b) * c -> * ( + a b ) c
+/// a + -b -> + a -b
+/// ...
+pub fn translate_infix(infix_notation: &str) -> Result<String, String> {
+    return translate_infix_with(infix_notation, &InfixOptions::default());
+}
+
+/// Function that translates a infix notated equation to an prefix notated equation.
+/// Parentheses in the input are kept, function calls become argument lists and, when enabled,
+/// juxtaposed operands are multiplied.
+/// # Example
+/// 2pi -> * 2 pi
+/// (a + b)(a - b) -> * ( + a b ) ( - a b )
+/// max(a, b + c) -> ( max a + b c )
+/// [x + y, 2] -> ( list + x y 2 )
 /// ...
-pub fn translate_infix(infix_notation: &str) -> String {
-    let whitespace = Regex::new(r"^\s+?").unwrap();
-    let mut ret = String::new();
-    let mut stack = Vec::new();
-    let word_or_punctuation = infix_notation
-        .split_word_bounds()
-        .filter(|x| !whitespace.is_match(x))
-        .rev();
-    let mut position = 0;
-    let mut skip = false;
-    for item in word_or_punctuation {
-        if skip {
-            skip = false;
-            position += 1;
-            continue;
+pub fn translate_infix_with(infix_notation: &str, options: &InfixOptions) -> Result<String, String> {
+    return parse_infix(infix_notation, options).map_err(|e| e.message);
+}
+
+/// Function that translates an infix notated equation as `translate_infix_with` does, but
+/// reports an error together with the span of the equation it concerns.
+pub fn parse_infix(infix_notation: &str, options: &InfixOptions) -> Result<String, CalcError> {
+    let tokens = token::tokenize(infix_notation)?;
+    if tokens.is_empty() {
+        return Ok(String::new());
+    }
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+        options,
+    };
+    let ret = parser.parse_expression(0)?;
+    match parser.peek() {
+        Some(token) => return Err(parser.unexpected(token)),
+        None => return Ok(ret),
+    }
+}
+
+/// Private struct that walks the tokens of an infix equation, building prefix notation as it goes.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    options: &'a InfixOptions,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        return self.tokens.get(self.position);
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let ret = self.tokens.get(self.position);
+        self.position += 1;
+        return ret;
+    }
+
+    /// Parses a run of operands joined by operators that bind at least as tight as `min_precedence`.
+    fn parse_expression(&mut self, min_precedence: i32) -> Result<String, CalcError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let (op, precedence, implicit) = match self.peek() {
+                // ':' only ends the middle of a conditional, which parse_conditional reads.
+                Some(token) if token.text == ":" => break,
+                Some(token) if token.kind == TokenKind::Operator || is_word_operator(token) => {
+                    (token.text.as_str(), get_precedence(&token.text), false)
+                }
+                Some(token) if self.is_juxtaposed(token) => {
+                    let precedence = if self.options.implicit_binds_tighter { 9 } else { 8 };
+                    ("*", precedence, true)
+                }
+                _ => break,
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            if !implicit {
+                self.next();
+            }
+            if op == "?" {
+                left = self.parse_conditional(left)?;
+                continue;
+            }
+            // '=' and '^' are right associative, everything else is left associative.
+            let next_precedence = if op == "=" || op == "^" { precedence } else { precedence + 1 };
+            let right = self.parse_expression(next_precedence)?;
+            left = format!("{} {} {}", op, left, right);
         }
-        match item {
-            ")" => {
-                ret.push_str(" ");
-                ret.push_str(item);
-                stack.push(item);
-            }
-            "=" | "+" | "-" | "*" | "/" => {
-                if stack.is_empty() {
-                    stack.push(item);
-                } else {
-                    let stack_top = stack.pop().unwrap();
-                    if get_precedence(stack_top) >= get_precedence(item) {
-                        if item != ")" {
-                            ret.push_str(" ");
-                            ret.push_str(stack_top);
-                        }
-                    } else {
-                        stack.push(stack_top);
-                    }
-                    stack.push(item);
+        return Ok(left);
+    }
+
+    /// Parses the rest of a conditional whose '?' has been read, e.g. x < 0 ? -x : x, as a call
+    /// of if. Like '=' it is right associative, so a ? b : c ? d : e needs no parentheses.
+    fn parse_conditional(&mut self, condition: String) -> Result<String, CalcError> {
+        let then = self.parse_expression(0)?;
+        match self.next() {
+            Some(colon) if colon.text == ":" => {}
+            Some(other) => return Err(self.unexpected(other)),
+            None => return Err(self.at_end("Missing ':' of a conditional, e.g. c ? a : b.")),
+        }
+        let otherwise = self.parse_expression(get_precedence("?"))?;
+        return Ok(format!("( if {} {} {} )", condition, then, otherwise));
+    }
+
+    /// Parses an operand, folding a leading sign into numbers and names.
+    fn parse_unary(&mut self) -> Result<String, CalcError> {
+        if self.peek().is_some_and(|token| token.kind == TokenKind::Identifier && token.text == "not") {
+            self.next();
+            // not binds looser than a comparison, so not a < b is not (a < b).
+            let operand = self.parse_expression(get_precedence("<"))?;
+            return Ok(format!("( not {} )", operand));
+        }
+        let sign = match self.peek() {
+            Some(token) if token.kind == TokenKind::Operator && (token.text == "-" || token.text == "+") => {
+                token.text.as_str()
+            }
+            _ => return self.parse_primary(),
+        };
+        self.next();
+        // A sign binds looser than '^', so -x^2 is -(x^2) rather than a signed name squared.
+        let raised = self.tokens.get(self.position + 1).is_some_and(|next| next.text == "^");
+        match self.peek() {
+            Some(token) if !raised && (token.kind == TokenKind::Number || (token.kind == TokenKind::Identifier && !calculator::is_function(&token.text))) => {
+                self.next();
+                if sign == "-" {
+                    return Ok(format!("-{}", token.text));
                 }
+                return Ok(token.text.clone());
             }
-            "(" => {
-                if stack.is_empty() {
-                    ret.push_str(" ");
-                    ret.push_str(item);
-                } else {
-                    let mut check = true;
-                    while check {
-                        if stack.is_empty() {
-                            check = false;
-                        } else {
-                            let stack_top = stack.pop().unwrap();
-                            if stack_top == ")" {
-                                check = false;
-                            } else {
-                                ret.push_str(" ");
-                                ret.push_str(stack_top);
-                            }
-                        }
+            _ => {
+                let operand = self.parse_expression(get_precedence("^"))?;
+                if sign == "-" {
+                    return Ok(format!("( - {} )", operand));
+                }
+                return Ok(operand);
+            }
+        }
+    }
+
+    /// Parses a number, a name, a function call or a parenthesized group.
+    fn parse_primary(&mut self) -> Result<String, CalcError> {
+        let token = match self.next() {
+            Some(token) => token,
+            None => return Err(self.at_end("Unexpected end of equation.")),
+        };
+        match token.kind {
+            TokenKind::Number | TokenKind::Date => {
+                return Ok(token.text.clone());
+            }
+            TokenKind::Identifier if calculator::is_function(&token.text) => {
+                match self.next() {
+                    Some(open) if open.kind == TokenKind::Open => {}
+                    _ => {
+                        let message = format!("Function {} needs an argument list, e.g. {}(x).", token.text, token.text);
+                        return Err(CalcError::new(message, token.start, token.end));
                     }
-                    ret.push_str(" ");
-                    ret.push_str(item);
                 }
+                let mut ret = format!("( {}", token.text);
+                if self.peek().is_some_and(|close| close.kind == TokenKind::Close) {
+                    self.next();
+                    ret.push_str(" )");
+                    return Ok(ret);
+                }
+                loop {
+                    ret.push(' ');
+                    ret.push_str(&self.parse_expression(0)?);
+                    match self.next() {
+                        Some(separator) if separator.kind == TokenKind::Comma => {}
+                        Some(close) if close.kind == TokenKind::Close => break,
+                        Some(other) => return Err(self.unexpected(other)),
+                        None => return Err(self.at_end("Unexpected end of equation.")),
+                    }
+                }
+                ret.push_str(" )");
+                return Ok(ret);
+            }
+            TokenKind::Identifier => {
+                return self.parse_indexes(token.text.clone());
+            }
+            TokenKind::Open if token.text == "[" => {
+                let list = self.parse_group(true)?;
+                return self.parse_indexes(list);
+            }
+            TokenKind::Open => {
+                return self.parse_group(false);
             }
             _ => {
-                let signed = check_signed(infix_notation, position);
-                match signed {
-                    Some("+") | Some("-") => {
-                        ret.push_str(" ");
-                        ret.push_str(item);
-                        ret.push_str(signed.unwrap());
-                        skip = true;
-                    },
-                    _ => {
-                        ret.push_str(" ");
-                        ret.push_str(item);
+                return Err(self.unexpected(token));
+            }
+        }
+    }
+
+    /// Parses the rest of a bracketed group, whose opening bracket has been read. A group with
+    /// commas in it is a list, e.g. [x, y], and so is any group in square brackets, e.g. [5].
+    fn parse_group(&mut self, list: bool) -> Result<String, CalcError> {
+        let inner = self.parse_expression(0)?;
+        let mut items = Vec::<String>::new();
+        loop {
+            match self.next() {
+                Some(separator) if separator.kind == TokenKind::Comma => {
+                    items.push(self.parse_expression(0)?);
+                }
+                Some(close) if close.kind == TokenKind::Close && items.is_empty() && !list => {
+                    return Ok(format!("( {} )", inner));
+                }
+                Some(close) if close.kind == TokenKind::Close => {
+                    items.insert(0, inner);
+                    return Ok(format!("( list {} )", items.join(" ")));
+                }
+                Some(other) => return Err(self.unexpected(other)),
+                None => return Err(self.at_end("Unexpected end of equation.")),
+            }
+        }
+    }
+
+    /// Parses indexes written straight after a name or a list, e.g. v[2] or m[1, 2]. With a
+    /// space in between, e.g. v [2], the bracket is an operand to multiply by instead.
+    fn parse_indexes(&mut self, operand: String) -> Result<String, CalcError> {
+        let mut ret = operand;
+        loop {
+            match self.peek() {
+                Some(open) if open.text == "[" && open.start == self.tokens[self.position - 1].end => {
+                    self.next();
+                    let mut indexes = vec![self.parse_expression(0)?];
+                    loop {
+                        match self.next() {
+                            Some(separator) if separator.kind == TokenKind::Comma => {
+                                indexes.push(self.parse_expression(0)?);
+                            }
+                            Some(close) if close.kind == TokenKind::Close => break,
+                            Some(other) => return Err(self.unexpected(other)),
+                            None => return Err(self.at_end("Unexpected end of equation.")),
+                        }
                     }
+                    ret = format!("( index {} {} )", ret, indexes.join(" "));
                 }
+                _ => return Ok(ret),
             }
         }
-        position += 1;
     }
-    while !stack.is_empty() {
-        let stack_top = stack.pop().unwrap();
-        ret.push_str(" ");
-        ret.push_str(stack_top);
+
+    /// Reports whether a token directly following a complete operand starts another operand
+    /// that should be multiplied with it.
+    fn is_juxtaposed(&self, token: &Token) -> bool {
+        if !self.options.implicit_multiplication {
+            return false;
+        }
+        match token.kind {
+            TokenKind::Identifier => return !is_word_operator(token),
+            TokenKind::Open => return true,
+            // Two numbers in a row are much more likely a typo than a product.
+            TokenKind::Number | TokenKind::Date => {
+                let previous = self.tokens[self.position - 1].kind;
+                return previous != TokenKind::Number && previous != TokenKind::Date;
+            }
+            _ => return false,
+        }
     }
 
-    let rev_ret = ret.split_word_bounds()
-        .rev()
-        .collect::<String>()
-        .trim()
-        .to_string();
-    return rev_ret;
-}
+    fn unexpected(&self, token: &Token) -> CalcError {
+        let message = match token.kind {
+            TokenKind::Number | TokenKind::Date | TokenKind::Identifier | TokenKind::Open => {
+                format!("Missing operator before '{}' at position {}.", token.text, token.start + 1)
+            }
+            _ => format!("Unexpected '{}' at position {}.", token.text, token.start + 1),
+        };
+        return CalcError::new(message, token.start, token.end);
+    }
 
-fn get_precedence(op: &str) -> i32 {
-    match op {
-        "=" => 3,
-        "*" | "/" => 2,
-        "+" | "-" => 1,
-        _ => 0,
+    /// Reports an error found where the equation stops.
+    fn at_end(&self, message: &str) -> CalcError {
+        let end = self.tokens.last().map_or(0, |token| token.end);
+        return CalcError::new(String::from(message), end, end);
     }
 }
 
-fn check_signed(infix_notation: &str, position: usize) -> Option<&str> {
-    let whitespace = Regex::new(r"^\s+?").unwrap();
-    let mut local = infix_notation
-        .split_word_bounds()
-        .filter(|x| !whitespace.is_match(x))
-        .rev();
-    let local_first_item = local.nth(position + 1);
-    let local_second_item = local.next();
-
-    match local_first_item {
-        None => {
-            return None;
-        },
-        _ => {
-            match local_second_item {
-                Some("+") | Some("-") | Some("*") | Some("/") | Some("=") => {
-                    return local_first_item;
-                },
-                None => {
-                    return None;
-                },
-                _ => {
-                    return None;
-                },
+/// Function that prints an equation tree in prefix notation.
+pub fn print_prefix(expression: &Expression) -> String {
+    match *expression {
+        Expression::Value(ref val) => {
+            return format!("{}", val);
+        }
+        Expression::Variable(ref val) => {
+            return val.clone();
+        }
+        Expression::Operator(ref op, ref operands) if operands.len() == 2 => {
+            let mut ret = op.clone();
+            for operand in operands.iter() {
+                ret.push(' ');
+                ret.push_str(&print_prefix(operand));
+            }
+            return ret;
+        }
+        Expression::Operator(ref name, ref args) | Expression::Function(ref name, ref args) => {
+            // Anything but a binary operator needs its argument list delimited.
+            let mut ret = String::from("( ");
+            ret.push_str(name);
+            for arg in args.iter() {
+                ret.push(' ');
+                ret.push_str(&print_prefix(arg));
             }
+            ret.push_str(" )");
+            return ret;
         }
     }
 }
 
+/// Private function that reports whether a name is an operator spelt as a word, i.e. and, or, in.
+fn is_word_operator(token: &Token) -> bool {
+    return token.kind == TokenKind::Identifier && (token.text == "and" || token.text == "or" || token.text == "in");
+}
+
+fn get_precedence(op: &str) -> i32 {
+    match op {
+        "^" => 10,
+        "*" | "/" => 8,
+        "+" | "-" => 7,
+        "in" => 6,
+        "==" | "!=" | "<" | "<=" | ">" | ">=" => 5,
+        "and" => 4,
+        "or" => 3,
+        "?" => 2,
+        "=" => 1,
+        _ => 0,
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use expression;
 
     #[test]
     fn test_simple_add() {
         let result = "+ 2 2";
         let input = "2 + 2";
-        assert_eq!(result, translate_infix(input));
+        assert_eq!(result, translate_infix(input).unwrap());
     }
 
     #[test]
     fn test_simple_subtract() {
         let result = "- 2 2";
         let input = "2 - 2";
-        assert_eq!(result, translate_infix(input));
+        assert_eq!(result, translate_infix(input).unwrap());
     }
 
     #[test]
     fn test_simple_multiply() {
         let result = "* 2 2";
         let input = "2 * 2";
-        assert_eq!(result, translate_infix(input));
+        assert_eq!(result, translate_infix(input).unwrap());
     }
 
     #[test]
     fn test_simple_divide() {
         let result = "/ 2 2";
         let input = "2 / 2";
-        assert_eq!(result, translate_infix(input));
+        assert_eq!(result, translate_infix(input).unwrap());
     }
 
     #[test]
     fn test_complex_one() {
         let input = "2 + pi / 35";
         let result = "+ 2 / pi 35";
-        assert_eq!(result, translate_infix(input));
+        assert_eq!(result, translate_infix(input).unwrap());
     }
 
     #[test]
     fn test_complex_two() {
         let input = "a + b * c / d";
-        let result = "+ a * b / c d";
-        assert_eq!(result, translate_infix(input));
+        let result = "+ a / * b c d";
+        assert_eq!(result, translate_infix(input).unwrap());
     }
     #[test]
     fn test_complex_three() {
         let input = "(a + b * c) / (d - f / g)";
         let result = "/ ( + a * b c ) ( - d / f g )";
-        assert_eq!(result, translate_infix(input));
+        assert_eq!(result, translate_infix(input).unwrap());
     }
     #[test]
     fn test_complex_four() {
         let input = "(a + b * c / (d - f / (g * h / i)))";
-        let result = "( + a * b / c ( - d / f ( * g / h i ) ) )";
-        assert_eq!(result, translate_infix(input));
+        let result = "( + a / * b c ( - d / f ( / * g h i ) ) )";
+        assert_eq!(result, translate_infix(input).unwrap());
     }
     #[test]
     fn test_complex_five() {
         let input = "(j + k) * (a + b * c / (d - f / (g * h / i)))";
-        let result = "* ( + j k ) ( + a * b / c ( - d / f ( * g / h i ) ) )";
-        assert_eq!(result, translate_infix(input));
+        let result = "* ( + j k ) ( + a / * b c ( - d / f ( / * g h i ) ) )";
+        assert_eq!(result, translate_infix(input).unwrap());
     }
     #[test]
     fn test_complex_six() {
         let input = "(a + b * c / (d - f / (g * h / i))) + (j + k)";
-        let result = "+ ( + a * b / c ( - d / f ( * g / h i ) ) ) ( + j k )";
-        assert_eq!(result, translate_infix(input));
+        let result = "+ ( + a / * b c ( - d / f ( / * g h i ) ) ) ( + j k )";
+        assert_eq!(result, translate_infix(input).unwrap());
     }
     #[test]
     fn test_prefix_equals() {
         let input = "a = 3";
         let result = "= a 3";
-        assert_eq!(result, translate_infix(input));
+        assert_eq!(result, translate_infix(input).unwrap());
     }
 
     #[test]
     fn test_prefix_equals_negative() {
         let input = "a = -3";
         let result = "= a -3";
-        assert_eq!(result, translate_infix(input));
+        assert_eq!(result, translate_infix(input).unwrap());
     }
 
     #[test]
     fn test_prefix_equals_negative_2() {
         let input = "(a + b * c / (d - f / (g * h / -i))) + (j + k)";
-        let result = "+ ( + a * b / c ( - d / f ( * g / h -i ) ) ) ( + j k )";
-        assert_eq!(result, translate_infix(input));
+        let result = "+ ( + a / * b c ( - d / f ( / * g h -i ) ) ) ( + j k )";
+        assert_eq!(result, translate_infix(input).unwrap());
+    }
+
+    #[test]
+    fn test_left_associative() {
+        assert_eq!("- - a b c", translate_infix("a - b - c").unwrap());
+        assert_eq!("+ - a b c", translate_infix("a - b + c").unwrap());
+        assert_eq!("- a ( - b c )", translate_infix("a - (b - c)").unwrap());
+    }
+
+    #[test]
+    fn test_equals_lowest_precedence() {
+        assert_eq!("= a + 3 4", translate_infix("a = 3 + 4").unwrap());
+        assert_eq!("= a = b 3", translate_infix("a = b = 3").unwrap());
+    }
+
+    #[test]
+    fn test_leading_negative() {
+        assert_eq!("+ -3 a", translate_infix("-3 + a").unwrap());
+        assert_eq!("* ( + -3 a ) 2", translate_infix("(-3 + a) * 2").unwrap());
+    }
+
+    #[test]
+    fn test_print_prefix_argument_lists() {
+        let expression = expression::from_prefix("(+ 1 2 3)").unwrap();
+        assert_eq!("( + 1 2 3 )", print_prefix(&expression));
+        let expression = expression::from_prefix("* (max a b c) sin x").unwrap();
+        assert_eq!("* ( max a b c ) ( sin x )", print_prefix(&expression));
+    }
+
+    #[test]
+    fn test_implicit_multiplication() {
+        assert_eq!("* 2 pi", translate_infix("2pi").unwrap());
+        assert_eq!("* 3 ( + a b )", translate_infix("3(a + b)").unwrap());
+        assert_eq!("* ( + a b ) ( - a b )", translate_infix("(a+b)(a-b)").unwrap());
+        assert_eq!("* * 2 x y", translate_infix("2x y").unwrap());
+        assert_eq!("+ * 2 x 1", translate_infix("2x + 1").unwrap());
+    }
+
+    #[test]
+    fn test_implicit_multiplication_precedence() {
+        let mut options = InfixOptions::default();
+        assert_eq!("* / 1 2 x", translate_infix_with("1/2x", &options).unwrap());
+        options.implicit_binds_tighter = true;
+        assert_eq!("/ 1 * 2 x", translate_infix_with("1/2x", &options).unwrap());
+        assert_eq!("* / a b c", translate_infix_with("a/b*c", &options).unwrap());
+    }
+
+    #[test]
+    fn test_implicit_multiplication_disabled() {
+        let options = InfixOptions {
+            implicit_multiplication: false,
+            ..InfixOptions::default()
+        };
+        assert_eq!(
+            Err(String::from("Missing operator before 'pi' at position 2.")),
+            translate_infix_with("2pi", &options)
+        );
+        assert_eq!(
+            Err(String::from("Missing operator before '3' at position 3.")),
+            translate_infix("2 3")
+        );
+    }
+
+    #[test]
+    fn test_function_calls() {
+        assert_eq!("( sin x )", translate_infix("sin(x)").unwrap());
+        assert_eq!("* 2 ( sin x )", translate_infix("2sin(x)").unwrap());
+        assert_eq!("( max a + b c 3 )", translate_infix("max(a, b + c, 3)").unwrap());
+        assert_eq!("* a ( + b c )", translate_infix("a(b + c)").unwrap());
+        assert_eq!(
+            Err(String::from("Function sin needs an argument list, e.g. sin(x).")),
+            translate_infix("sin x")
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        assert_eq!("( list x y )", translate_infix("(x, y)").unwrap());
+        assert_eq!("( list = + * 2 x y 5 = - x y 1 )", translate_infix("(2x + y = 5, x - y = 1)").unwrap());
+        assert_eq!("* 2 ( x )", translate_infix("2(x)").unwrap());
+        assert_eq!("( list 5 )", translate_infix("[5]").unwrap());
+        assert_eq!("( list ( list 1 2 3 ) )", translate_infix("[[1, 2, 3]]").unwrap());
+    }
+
+    #[test]
+    fn test_indexing() {
+        assert_eq!("( index v 2 )", translate_infix("v[2]").unwrap());
+        assert_eq!("( index ( index m 1 ) + i 1 )", translate_infix("m[1][i + 1]").unwrap());
+        assert_eq!("( index ( list 1 2 ) 1 )", translate_infix("[1, 2][1]").unwrap());
+        assert_eq!("* v ( list 2 )", translate_infix("v [2]").unwrap());
+    }
+
+    #[test]
+    fn test_unary_minus() {
+        assert_eq!("( - ( + a b ) )", translate_infix("-(a + b)").unwrap());
+        assert_eq!("* -2 x", translate_infix("-2x").unwrap());
+    }
+
+    #[test]
+    fn test_comparisons_and_logic() {
+        assert_eq!("< + x 1 * 2 y", translate_infix("x + 1 < 2y").unwrap());
+        assert_eq!("or and < a b > b c == d 0", translate_infix("a < b and b > c or d == 0").unwrap());
+        assert_eq!("( not < a b )", translate_infix("not a < b").unwrap());
+        assert_eq!("and ( not p ) q", translate_infix("not p and q").unwrap());
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!("in - 2026-12-01 2026-10-18 days", translate_infix("2026-12-01 - 2026-10-18 in days").unwrap());
+        assert_eq!("+ now * 3 weeks", translate_infix("now + 3 weeks").unwrap());
+        assert_eq!("> in - b a days 3", translate_infix("b - a in days > 3").unwrap());
+    }
+
+    #[test]
+    fn test_conditional() {
+        assert_eq!("= y ( if < x 0 -x x )", translate_infix("y = x < 0 ? -x : x").unwrap());
+        assert_eq!("( if a 1 ( if b 2 3 ) )", translate_infix("a ? 1 : b ? 2 : 3").unwrap());
+        assert_eq!("+ ( ( if c 1 2 ) ) 3", translate_infix("(c ? 1 : 2) + 3").unwrap());
+        assert_eq!("( if > x 0 x 0 )", translate_infix("if(x > 0, x, 0)").unwrap());
+        assert!(translate_infix("c ? 1").is_err());
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(Err(String::from("Unexpected end of equation.")), translate_infix("a +"));
+        assert_eq!(Err(String::from("Unexpected '*' at position 5.")), translate_infix("a + * b"));
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let options = InfixOptions::default();
+        let span = |input: &str| parse_infix(input, &options).map(|_| ()).map_err(|e| (e.start, e.end));
+        assert_eq!(Err((4, 5)), span("a + * b"));
+        assert_eq!(Err((3, 3)), span("a + "));
+        assert_eq!(Err((4, 7)), span("2 + sin 3"));
+        assert_eq!(Err((2, 3)), span("a @ b"));
+        assert_eq!(Ok(()), span("sin(a) + b"));
     }
 }
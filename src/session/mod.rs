@@ -0,0 +1,147 @@
+#![allow(clippy::needless_return)]
+
+use memory::Memory;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::collections::BTreeMap;
+
+/// Struct that holds what a saved session keeps: the variables, the lists, the lines entered and
+/// the memory. It is saved as JSON; fields left out of the JSON are empty on restoring.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Saved {
+    pub variables: BTreeMap<String, f64>,
+    pub lists: BTreeMap<String, Vec<f64>>,
+    pub inputs: Vec<String>,
+    pub memory: Memory,
+}
+
+impl Saved {
+    /// Function that writes the session as JSON.
+    pub fn to_json(&self) -> String {
+        // Keys are strings or numbers, so writing cannot fail.
+        return serde_json::to_string(self).unwrap();
+    }
+
+    /// Function that reads a session written by `to_json`.
+    pub fn from_json(json: &str) -> Result<Saved, String> {
+        return serde_json::from_str::<Saved>(json).map_err(|e| format!("Invalid session: {}", e));
+    }
+}
+
+/// Struct that remembers the lines entered in a session, oldest first, and which of them is
+/// being recalled. Up and down in the widget walk through it the way a shell does: going back
+/// keeps what was being typed, and going forward past the newest line brings it back.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History {
+    entries: Vec<String>,
+    position: Option<usize>,
+    draft: String,
+}
+
+impl History {
+    pub fn new() -> History {
+        return History::default();
+    }
+
+    /// Function that starts a history with lines entered before, oldest first.
+    pub fn with_entries(entries: Vec<String>) -> History {
+        return History { entries, position: None, draft: String::new() };
+    }
+
+    /// Function that returns the lines entered so far, oldest first.
+    pub fn entries(&self) -> &[String] {
+        return &self.entries;
+    }
+
+    /// Function that records an entered line and stops recalling. Blank lines and repeats of
+    /// the line before are left out.
+    pub fn push(&mut self, input: &str) {
+        self.reset();
+        let input = input.trim();
+        if input.is_empty() || self.entries.last().map(|last| last.as_str()) == Some(input) {
+            return;
+        }
+        self.entries.push(String::from(input));
+    }
+
+    /// Function that returns the line entered before the one being recalled, or the newest
+    /// line when nothing is being recalled yet, in which case `current` is kept to come back
+    /// to. Returns None at the oldest line.
+    pub fn recall_previous(&mut self, current: &str) -> Option<String> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = String::from(current);
+                self.entries.len() - 1
+            }
+            Some(0) => return None,
+            Some(position) => position - 1,
+        };
+        self.position = Some(position);
+        return Some(self.entries[position].clone());
+    }
+
+    /// Function that returns the line entered after the one being recalled, or what was being
+    /// typed when the newest line is passed. Returns None when nothing is being recalled.
+    pub fn recall_next(&mut self) -> Option<String> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            return Some(self.entries[position + 1].clone());
+        }
+        self.position = None;
+        return Some(std::mem::take(&mut self.draft));
+    }
+
+    /// Function that stops recalling, so that the next `recall_previous` starts from the newest
+    /// line.
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved() {
+        let mut saved = Saved::default();
+        saved.variables.insert(String::from("x"), 2.5);
+        saved.inputs.push(String::from("x = 2.5"));
+        saved.memory.registers.insert(3, 7.0);
+        assert_eq!(Ok(saved.clone()), Saved::from_json(&saved.to_json()));
+        assert_eq!(Ok(Saved::default()), Saved::from_json("{}"));
+        assert!(Saved::from_json("{\"variables\": []}").unwrap_err().starts_with("Invalid session"));
+    }
+
+    #[test]
+    fn test_push() {
+        let mut history = History::new();
+        history.push("1 + 2");
+        history.push("  ");
+        history.push("1 + 2 ");
+        history.push("x = 3");
+        assert_eq!(&[String::from("1 + 2"), String::from("x = 3")], history.entries());
+    }
+
+    #[test]
+    fn test_previous_and_next() {
+        let mut history = History::new();
+        assert_eq!(None, history.recall_previous("draft"));
+        history.push("a");
+        history.push("b");
+        assert_eq!(None, history.recall_next());
+        assert_eq!(Some(String::from("b")), history.recall_previous("dra"));
+        assert_eq!(Some(String::from("a")), history.recall_previous("b"));
+        assert_eq!(None, history.recall_previous("a"));
+        assert_eq!(Some(String::from("b")), history.recall_next());
+        assert_eq!(Some(String::from("dra")), history.recall_next());
+        assert_eq!(None, history.recall_next());
+        assert_eq!(Some(String::from("b")), history.recall_previous(""));
+        history.push("c");
+        assert_eq!(Some(String::from("c")), history.recall_previous(""));
+    }
+}
@@ -0,0 +1,423 @@
+#![allow(clippy::needless_return)]
+
+use calculator;
+use expression::Expression;
+use infix;
+use integrate;
+use std::collections::HashMap;
+
+/// Function that simplifies an equation tree without needing every variable to have a value.
+/// Variables found in the hash map are replaced by their values, constant sub-equations are
+/// folded, and sums and products are collected so that identities such as x + 0, x * 1 and
+/// x - x disappear and like terms are combined.
+/// # Example
+/// x + 0 -> x
+/// 2 * x + 3 * x -> 5 * x
+/// x + y - x -> y
+/// ...
+pub fn simplify(expression: &Expression, map: &HashMap<String, f64>) -> Expression {
+    match *expression {
+        Expression::Value(ref val) => {
+            return Expression::Value(*val);
+        }
+        Expression::Variable(ref name) => match map.get(name) {
+            Some(val) => return Expression::Value(*val),
+            None => return expression.clone(),
+        },
+        Expression::Function(ref name, ref args) if integrate::binds(name, args) => {
+            // The dummy variable keeps its own value inside, e.g. the k of sum(k^2, k, 1, 10).
+            let mut inner = map.clone();
+            if let Some(Expression::Variable(ref variable)) = args.get(1) {
+                inner.remove(variable);
+            }
+            let mut args = args.clone();
+            args[0] = simplify(&args[0], &inner);
+            for arg in args.iter_mut().skip(2) {
+                *arg = simplify(arg, map);
+            }
+            return Expression::Function(name.clone(), args);
+        }
+        Expression::Function(ref name, ref args) => {
+            let args = args.iter().map(|arg| simplify(arg, map)).collect::<Vec<Expression>>();
+            if name == "ln" && args == [Expression::Variable(String::from("e"))] {
+                // The constant e stays a name so it prints as e, but ln(e) is exactly 1.
+                return Expression::Value(1.0);
+            }
+            return fold_constants(name, args, true);
+        }
+        Expression::Operator(ref op, ref operands) => {
+            let operands = operands.iter().map(|operand| simplify(operand, map)).collect::<Vec<Expression>>();
+            match op.as_str() {
+                "+" | "-" => return simplify_sum(op, &operands),
+                "*" => return simplify_product(&operands),
+                "/" => return simplify_quotient(operands),
+                "^" => return simplify_power(operands),
+                _ => return fold_constants(op, operands, false),
+            }
+        }
+    }
+}
+
+/// Function that simplifies an equation tree and prints the result in infix notation.
+pub fn simplify_to_infix(expression: &Expression, map: &HashMap<String, f64>) -> String {
+    return infix::print_infix(&simplify(expression, map));
+}
+
+/// Private function that evaluates an operator or function whose arguments are all values.
+fn fold_constants(name: &str, args: Vec<Expression>, is_function: bool) -> Expression {
+    let mut values = Vec::<f64>::new();
+    for arg in args.iter() {
+        match *arg {
+            Expression::Value(ref val) => values.push(*val),
+            _ => break,
+        }
+    }
+    if values.len() == args.len() {
+        if let Ok(val) = calculator::process_function(name, &values) {
+            return Expression::Value(val);
+        }
+    }
+    if is_function {
+        return Expression::Function(String::from(name), args);
+    }
+    return Expression::Operator(String::from(name), args);
+}
+
+/// Private function that collects a sum into a constant plus a list of distinct terms with
+/// their coefficients, then rebuilds it.
+fn simplify_sum(op: &str, operands: &[Expression]) -> Expression {
+    let mut terms = Vec::<(f64, Expression)>::new();
+    let mut constant = 0.0;
+    for (index, operand) in operands.iter().enumerate() {
+        let sign = if op == "-" && (index > 0 || operands.len() == 1) { -1.0 } else { 1.0 };
+        add_term(sign, operand, &mut terms, &mut constant);
+    }
+
+    let mut ret: Option<Expression> = None;
+    for (coefficient, term) in terms.into_iter() {
+        if coefficient == 0.0 {
+            continue;
+        }
+        ret = Some(match ret {
+            None if coefficient == -1.0 => Expression::Operator(String::from("-"), vec![term]),
+            None => scale(coefficient, term),
+            Some(sum) if coefficient < 0.0 => binary("-", sum, scale(-coefficient, term)),
+            Some(sum) => binary("+", sum, scale(coefficient, term)),
+        });
+    }
+    match ret {
+        None => return Expression::Value(constant),
+        Some(sum) if constant < 0.0 => return binary("-", sum, Expression::Value(-constant)),
+        Some(sum) if constant > 0.0 => return binary("+", sum, Expression::Value(constant)),
+        Some(sum) => return sum,
+    }
+}
+
+fn add_term(sign: f64, expression: &Expression, terms: &mut Vec<(f64, Expression)>, constant: &mut f64) {
+    match *expression {
+        Expression::Value(ref val) => {
+            *constant += sign * val;
+        }
+        Expression::Operator(ref op, ref operands) if op == "+" => {
+            for operand in operands.iter() {
+                add_term(sign, operand, terms, constant);
+            }
+        }
+        Expression::Operator(ref op, ref operands) if op == "-" => {
+            for (index, operand) in operands.iter().enumerate() {
+                let inner = if index > 0 || operands.len() == 1 { -sign } else { sign };
+                add_term(inner, operand, terms, constant);
+            }
+        }
+        _ => {
+            let (coefficient, term) = split_coefficient(expression);
+            match terms.iter_mut().find(|existing| existing.1 == term) {
+                Some(existing) => existing.0 += sign * coefficient,
+                None => terms.push((sign * coefficient, term)),
+            }
+        }
+    }
+}
+
+/// Private function that multiplies out the constant factors of a product, drops factors of one
+/// and puts the remaining factors in a fixed order so that like terms compare equal.
+fn simplify_product(operands: &[Expression]) -> Expression {
+    let mut coefficient = 1.0;
+    let mut factors = Vec::<Expression>::new();
+    for operand in operands.iter() {
+        collect_factors(operand, &mut coefficient, &mut factors);
+    }
+    if coefficient == 0.0 || factors.is_empty() {
+        return Expression::Value(coefficient);
+    }
+    return from_powers(coefficient, gather_powers(factors));
+}
+
+/// Private function that gathers repeated bases of a list of factors, so x * x^2 becomes
+/// x to the power 3.
+fn gather_powers(factors: Vec<Expression>) -> Vec<(Expression, f64)> {
+    let mut powers = Vec::<(Expression, f64)>::new();
+    for factor in factors.into_iter() {
+        let (base, exponent) = match factor {
+            Expression::Operator(ref op, ref operands) if op == "^" => match operands[1] {
+                Expression::Value(exponent) => (operands[0].clone(), exponent),
+                _ => (factor.clone(), 1.0),
+            },
+            _ => (factor, 1.0),
+        };
+        match powers.iter_mut().find(|existing| existing.0 == base) {
+            Some(existing) => existing.1 += exponent,
+            None => powers.push((base, exponent)),
+        }
+    }
+    return powers;
+}
+
+/// Private function that multiplies a coefficient by bases raised to their exponents, dropping
+/// exponents of zero and putting the factors in a fixed order.
+fn from_powers(coefficient: f64, powers: Vec<(Expression, f64)>) -> Expression {
+    let mut factors = Vec::<Expression>::new();
+    for (base, exponent) in powers.into_iter() {
+        if exponent == 1.0 {
+            factors.push(base);
+        } else if exponent != 0.0 {
+            factors.push(binary("^", base, Expression::Value(exponent)));
+        }
+    }
+    if factors.is_empty() {
+        return Expression::Value(coefficient);
+    }
+    factors.sort_by_key(infix::print_infix);
+    let product = multiply(factors);
+    return scale(coefficient, product.unwrap());
+}
+
+fn collect_factors(expression: &Expression, coefficient: &mut f64, factors: &mut Vec<Expression>) {
+    match *expression {
+        Expression::Value(ref val) => {
+            *coefficient *= val;
+        }
+        Expression::Operator(ref op, ref operands) if op == "*" => {
+            for operand in operands.iter() {
+                collect_factors(operand, coefficient, factors);
+            }
+        }
+        Expression::Operator(ref op, ref operands) if op == "-" && operands.len() == 1 => {
+            *coefficient = -*coefficient;
+            collect_factors(&operands[0], coefficient, factors);
+        }
+        _ => {
+            factors.push(expression.clone());
+        }
+    }
+}
+
+/// Private function that simplifies a quotient, folding constants and cancelling the factors
+/// that the numerator and denominator share, e.g. y / y^2 becomes 1 / y.
+fn simplify_quotient(operands: Vec<Expression>) -> Expression {
+    if operands.len() != 2 {
+        return fold_constants("/", operands, false);
+    }
+    let mut iter = operands.into_iter();
+    let numerator = iter.next().unwrap();
+    let denominator = iter.next().unwrap();
+    match (&numerator, &denominator) {
+        (&Expression::Value(_), &Expression::Value(_)) => {
+            return fold_constants("/", vec![numerator, denominator], false);
+        }
+        (_, &Expression::Value(1.0)) => {
+            return numerator;
+        }
+        (&Expression::Value(0.0), _) => {
+            return Expression::Value(0.0);
+        }
+        _ if numerator == denominator => {
+            return Expression::Value(1.0);
+        }
+        _ => {
+            return cancel_factors(numerator, denominator);
+        }
+    }
+}
+
+/// Private function that divides out the bases a numerator and denominator have in common.
+/// Leaves the quotient as it is when they share none.
+fn cancel_factors(numerator: Expression, denominator: Expression) -> Expression {
+    let (mut top_coefficient, mut top_factors) = (1.0, Vec::<Expression>::new());
+    collect_factors(&numerator, &mut top_coefficient, &mut top_factors);
+    let (mut bottom_coefficient, mut bottom_factors) = (1.0, Vec::<Expression>::new());
+    collect_factors(&denominator, &mut bottom_coefficient, &mut bottom_factors);
+    let mut top = gather_powers(top_factors);
+    let mut bottom = Vec::<(Expression, f64)>::new();
+    let mut cancelled = false;
+    for (base, exponent) in gather_powers(bottom_factors).into_iter() {
+        match top.iter_mut().find(|existing| existing.0 == base) {
+            Some(existing) => {
+                existing.1 -= exponent;
+                cancelled = true;
+            }
+            None => bottom.push((base, exponent)),
+        }
+    }
+    if !cancelled {
+        return binary("/", numerator, denominator);
+    }
+    // A base left with a negative exponent moves to the denominator.
+    for power in top.iter_mut().filter(|power| power.1 < 0.0) {
+        bottom.push((power.0.clone(), -power.1));
+        power.1 = 0.0;
+    }
+    if bottom.is_empty() {
+        return from_powers(top_coefficient / bottom_coefficient, top);
+    }
+    let numerator = from_powers(top_coefficient, top);
+    let denominator = from_powers(bottom_coefficient, bottom);
+    return simplify_quotient(vec![numerator, denominator]);
+}
+
+/// Private function that simplifies a power, folding constants and dropping exponents of one and zero.
+fn simplify_power(operands: Vec<Expression>) -> Expression {
+    let mut iter = operands.into_iter();
+    let base = iter.next().unwrap();
+    let exponent = iter.next().unwrap();
+    match (&base, &exponent) {
+        (&Expression::Value(_), &Expression::Value(_)) => {
+            return fold_constants("^", vec![base, exponent], false);
+        }
+        (_, &Expression::Value(0.0)) | (&Expression::Value(1.0), _) => {
+            return Expression::Value(1.0);
+        }
+        (_, &Expression::Value(1.0)) => {
+            return base;
+        }
+        _ => {
+            return binary("^", base, exponent);
+        }
+    }
+}
+
+/// Private function that splits a term into its constant coefficient and the rest of the term.
+fn split_coefficient(expression: &Expression) -> (f64, Expression) {
+    let mut coefficient = 1.0;
+    let mut factors = Vec::<Expression>::new();
+    collect_factors(expression, &mut coefficient, &mut factors);
+    if factors.is_empty() {
+        return (coefficient, Expression::Value(1.0));
+    }
+    let term = multiply(factors);
+    return (coefficient, term.unwrap());
+}
+
+fn scale(coefficient: f64, term: Expression) -> Expression {
+    if coefficient == 1.0 {
+        return term;
+    }
+    if coefficient == -1.0 {
+        return Expression::Operator(String::from("-"), vec![term]);
+    }
+    let mut factors = vec![Expression::Value(coefficient)];
+    let mut ignored = 1.0;
+    collect_factors(&term, &mut ignored, &mut factors);
+    let product = multiply(factors);
+    return product.unwrap();
+}
+
+/// Private function that multiplies a list of factors together from the left.
+fn multiply(factors: Vec<Expression>) -> Option<Expression> {
+    return factors.into_iter().fold(None, |product, factor| match product {
+        None => Some(factor),
+        Some(product) => Some(binary("*", product, factor)),
+    });
+}
+
+fn binary(op: &str, left: Expression, right: Expression) -> Expression {
+    return Expression::Operator(String::from(op), vec![left, right]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expression;
+    use prefix;
+
+    fn simplify_infix(input: &str, map: &HashMap<String, f64>) -> String {
+        let prefix_notation = prefix::translate_infix(input).unwrap();
+        let expression = expression::from_prefix(&prefix_notation).unwrap();
+        return simplify_to_infix(&expression, map);
+    }
+
+    fn check(result: &str, input: &str) {
+        assert_eq!(result, simplify_infix(input, &HashMap::<String, f64>::new()));
+    }
+
+    #[test]
+    fn test_identities() {
+        check("x", "x + 0");
+        check("x", "0 + x");
+        check("x", "x * 1");
+        check("0", "x * 0");
+        check("0", "x - x");
+        check("x", "x / 1");
+        check("1", "x / x");
+        check("0", "0 / x");
+        check("1", "ln(e)");
+        check("x", "ln(e) * x");
+    }
+
+    #[test]
+    fn test_cancel_factors() {
+        check("1 / y", "y / y^2");
+        check("1 / y", "x / (x * y)");
+        check("x^2", "x^3 / x");
+        check("0.5 * y", "2 * x * y / (4 * x)");
+        check("x / y", "x / y");
+        check("-1 / x^2", "-x / x^3");
+    }
+
+    #[test]
+    fn test_fold_constants() {
+        check("14", "2 + 3 * 4");
+        check("x + 14", "x + 2 + 3 * 4");
+        check("6 * x", "3 * (2 * x)");
+        check("x", "sin(0) + x");
+        check("2 * x + 1", "max(1, 2) * x + 1");
+    }
+
+    #[test]
+    fn test_like_terms() {
+        check("5 * x", "2x + 3x");
+        check("y", "x + y - x");
+        check("0", "(x + 1) - (x + 1)");
+        check("2 * a * b - c", "a * b + b * a - c");
+        check("-x", "x - 2x");
+        check("-x - 3", "1 - x - 4");
+    }
+
+    #[test]
+    fn test_substitution() {
+        let mut map = HashMap::<String, f64>::new();
+        map.insert(String::from("a"), 2.0);
+        assert_eq!("2 * x + 6", simplify_infix("a * x + 3a", &map));
+        assert_eq!("x / 2", simplify_infix("x / a", &map));
+    }
+
+    #[test]
+    fn test_powers() {
+        check("x^3", "x * x^2");
+        check("1", "x^0");
+        check("x", "x^1");
+        check("8", "2^3");
+        check("2 * x^2", "x*x + x^2");
+        check("x^2 * y", "y * x * x");
+    }
+
+    #[test]
+    fn test_functions_kept() {
+        check("2 * sin(x)", "sin(x) + sin(x)");
+        let mut map = HashMap::<String, f64>::new();
+        map.insert(String::from("a"), 2.0);
+        map.insert(String::from("k"), 5.0);
+        map.insert(String::from("n"), 10.0);
+        assert_eq!("sum(k^2, k, 1, 10)", simplify_infix("sum(k^a, k, 1, n)", &map));
+    }
+}
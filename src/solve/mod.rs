@@ -0,0 +1,504 @@
+#![allow(clippy::needless_return)]
+
+use calculator;
+use calculator::Cell;
+use expression;
+use expression::Expression;
+use linear;
+use linear::LinearError;
+use std::collections::HashMap;
+
+/// Half the width of the range searched for roots, centred on the guess (or on 0 without one).
+const SEARCH_RADIUS: f64 = 100.0;
+/// Number of steps the search range is sampled at when looking for sign changes.
+const SEARCH_STEPS: usize = 4000;
+/// Largest number of Newton/secant or bisection steps taken for one root.
+const MAX_ITERATIONS: usize = 100;
+/// How close to zero an answer has to be to count as a root.
+const TOLERANCE: f64 = 1e-9;
+
+/// Enum that describes why an equation could not be solved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveError {
+    /// The equation or call is malformed, or uses a variable that has no value.
+    Invalid(String),
+    /// Newton's method and the secant method both failed to settle on a root from `start`.
+    NoConvergence { start: f64, iterations: usize },
+    /// No root was found between `low` and `high`.
+    NoRoot { low: f64, high: f64 },
+    /// The equation holds at every point sampled, e.g. x = x, so every value is a root.
+    Identity { variable: String },
+    /// The equations of a system contradict each other, so there is no solution.
+    Inconsistent,
+    /// The equations of a system do not pin down the listed variables.
+    Underdetermined { free: Vec<String> },
+}
+
+impl SolveError {
+    /// Function that describes the error in words.
+    pub fn message(&self) -> String {
+        match *self {
+            SolveError::Invalid(ref message) => return message.clone(),
+            SolveError::NoConvergence { start, iterations } => {
+                return format!("Did not converge on a root from {} after {} iterations.", start, iterations);
+            }
+            SolveError::NoRoot { low, high } => {
+                return format!("No root found between {} and {}.", low, high);
+            }
+            SolveError::Identity { ref variable } => {
+                return format!("The equation is true for every {}.", variable);
+            }
+            SolveError::Inconsistent => {
+                return String::from("The system is singular: the equations contradict each other.");
+            }
+            SolveError::Underdetermined { ref free } => {
+                return format!("The system is singular: {} can take any value.", free.join(", "));
+            }
+        }
+    }
+}
+
+/// Function that numerically solves an equation for one variable, using the hash map for every
+/// other name. An equation without '=' is solved for zero.
+/// Without a guess every root found between -100 and 100 is returned, smallest first. With a
+/// guess only the root nearest to it is returned.
+/// # Example
+/// x^2 - 2 = 0 -> [-1.4142135623730951, 1.4142135623730951]
+/// ...
+pub fn solve(equation: &Expression, variable: &str, guess: Option<f64>, map: &HashMap<String, f64>) -> Result<Vec<f64>, SolveError> {
+    let function = Function::new(equation, variable, map)?;
+    let centre = guess.unwrap_or(0.0);
+    function.check(centre)?;
+
+    if let Some(start) = guess {
+        if let Ok(root) = function.newton(start) {
+            return Ok(vec![root]);
+        }
+    }
+
+    let low = centre - SEARCH_RADIUS;
+    let high = centre + SEARCH_RADIUS;
+    let mut roots = function.scan(low, high)?;
+    if roots.is_empty() {
+        // Roots that touch zero without crossing it, e.g. x^2 = 0, have no sign change to find.
+        match function.newton(centre) {
+            Ok(root) => roots.push(root),
+            Err(e) if guess.is_some() => return Err(e),
+            Err(_) => return Err(SolveError::NoRoot { low, high }),
+        }
+    }
+    if guess.is_some() {
+        let nearest = roots
+            .into_iter()
+            .fold(None, |nearest: Option<f64>, root| match nearest {
+                Some(best) if (best - centre).abs() <= (root - centre).abs() => Some(best),
+                _ => Some(root),
+            });
+        return Ok(nearest.into_iter().collect());
+    }
+    return Ok(roots);
+}
+
+/// Function that evaluates a solve(equation, variable[, guess]) call.
+pub fn solve_call(args: &[Expression], map: &HashMap<String, f64>) -> Result<Vec<f64>, SolveError> {
+    let (equation, variable) = match args {
+        [ref equation, Expression::Variable(ref variable)] | [ref equation, Expression::Variable(ref variable), _] => {
+            (equation, variable)
+        }
+        _ => {
+            return Err(SolveError::Invalid(String::from(
+                "solve takes an equation, a variable and optionally a guess, e.g. solve(x^2 = 2, x, 1).",
+            )));
+        }
+    };
+    let guess = match args.get(2) {
+        Some(guess) => Some(evaluate(guess, map).map_err(SolveError::Invalid)?),
+        None => None,
+    };
+    return solve(equation, variable, guess, map);
+}
+
+/// Function that solves a system of linear equations for the listed variables, using the hash
+/// map for every other name. The answers are in the order of the variables.
+/// # Example
+/// [2x + y = 5, x - y = 1], [x, y] -> [2, 1]
+/// ...
+pub fn solve_system(equations: &[Expression], variables: &[String], map: &HashMap<String, f64>) -> Result<Vec<f64>, SolveError> {
+    for (index, variable) in variables.iter().enumerate() {
+        if variables[..index].contains(variable) {
+            return Err(SolveError::Invalid(format!("Variable {} is listed twice.", variable)));
+        }
+    }
+    let mut map = map.clone();
+    for variable in variables.iter() {
+        map.remove(variable);
+    }
+
+    let mut coefficients = Vec::<Vec<f64>>::new();
+    let mut constants = Vec::<f64>::new();
+    for (index, equation) in equations.iter().enumerate() {
+        let cells = difference(equation);
+        let at = |point: &[f64]| -> Result<f64, SolveError> {
+            let mut map = map.clone();
+            for (variable, val) in variables.iter().zip(point.iter()) {
+                map.insert(variable.clone(), *val);
+            }
+            match calculator::calculate(&cells, &map).map_err(SolveError::Invalid)?.pop() {
+                Some(Cell::Value(val)) => return Ok(val),
+                _ => return Err(SolveError::Invalid(String::from("Equation did not give a value."))),
+            }
+        };
+        // Reading the coefficients off at zero and at each unit vector, then checking them at
+        // one more point, catches equations such as x * y = 1 that are not linear.
+        let mut point = vec![0.0; variables.len()];
+        let constant = at(&point)?;
+        let mut row = Vec::<f64>::new();
+        for column in 0..variables.len() {
+            point[column] = 1.0;
+            row.push(at(&point)? - constant);
+            point[column] = 0.0;
+        }
+        let check = (0..variables.len()).map(|column| 1.5 + column as f64).collect::<Vec<f64>>();
+        let expected = constant + row.iter().zip(check.iter()).map(|(a, x)| a * x).sum::<f64>();
+        let actual = at(&check)?;
+        if !(actual - expected).abs().le(&(1e-9 * actual.abs().max(1.0))) {
+            return Err(SolveError::Invalid(format!(
+                "Equation {} is not linear in {}.",
+                index + 1,
+                variables.join(", ")
+            )));
+        }
+        coefficients.push(row);
+        constants.push(-constant);
+    }
+
+    match linear::solve_linear(&coefficients, &constants) {
+        Ok(answers) => return Ok(answers),
+        Err(LinearError::Inconsistent) => return Err(SolveError::Inconsistent),
+        Err(LinearError::Underdetermined { free }) => {
+            let free = free.into_iter().map(|column| variables[column].clone()).collect();
+            return Err(SolveError::Underdetermined { free });
+        }
+    }
+}
+
+/// Function that evaluates a solve([equation, ...], [variable, ...]) call, pairing every
+/// variable with its answer.
+pub fn solve_system_call(args: &[Expression], map: &HashMap<String, f64>) -> Result<Vec<(String, f64)>, SolveError> {
+    let usage = "solve takes a list of equations and a list of variables, e.g. solve([x + y = 3, x - y = 1], [x, y]).";
+    let (equations, variables) = match args {
+        [Expression::Function(ref list, ref equations), ref variables] if list == "list" => (equations, variables),
+        _ => return Err(SolveError::Invalid(String::from(usage))),
+    };
+    let variables = match *variables {
+        Expression::Function(ref list, ref variables) if list == "list" => variables.clone(),
+        Expression::Variable(_) => vec![variables.clone()],
+        _ => return Err(SolveError::Invalid(String::from(usage))),
+    };
+    let mut names = Vec::<String>::new();
+    for variable in variables.into_iter() {
+        match variable {
+            Expression::Variable(name) => names.push(name),
+            _ => return Err(SolveError::Invalid(String::from(usage))),
+        }
+    }
+    let answers = solve_system(equations, &names, map)?;
+    return Ok(names.into_iter().zip(answers).collect());
+}
+
+/// Function that evaluates an equation tree to a number, using the hash map to resolve variables.
+pub fn evaluate(expression: &Expression, map: &HashMap<String, f64>) -> Result<f64, String> {
+    match calculator::calculate(&expression::to_cells(expression), map)?.pop() {
+        Some(Cell::Value(val)) => return Ok(val),
+        _ => return Err(String::from("Equation did not give a value.")),
+    }
+}
+
+/// Private struct that evaluates left - right of an equation for a value of the variable.
+struct Function<'a> {
+    cells: Vec<Cell>,
+    variable: &'a str,
+    map: HashMap<String, f64>,
+}
+
+impl<'a> Function<'a> {
+    fn new(equation: &Expression, variable: &'a str, map: &HashMap<String, f64>) -> Result<Function<'a>, SolveError> {
+        let mut map = map.clone();
+        map.remove(variable);
+        return Ok(Function {
+            cells: difference(equation),
+            variable,
+            map,
+        });
+    }
+
+    /// Evaluates once so that errors such as an unknown variable are reported up front.
+    fn check(&self, x: f64) -> Result<(), SolveError> {
+        let mut map = self.map.clone();
+        map.insert(String::from(self.variable), x);
+        calculator::calculate(&self.cells, &map).map_err(SolveError::Invalid)?;
+        return Ok(());
+    }
+
+    /// Gives NaN wherever the equation has no value, e.g. ln(x) for x < 0.
+    fn value(&self, x: f64) -> f64 {
+        let mut map = self.map.clone();
+        map.insert(String::from(self.variable), x);
+        match calculator::calculate(&self.cells, &map) {
+            Ok(mut vec) => match vec.pop() {
+                Some(Cell::Value(val)) => return val,
+                _ => return f64::NAN,
+            },
+            Err(_) => return f64::NAN,
+        }
+    }
+
+    /// Newton's method with a numerical derivative, taking a secant step instead whenever the
+    /// derivative vanishes.
+    fn newton(&self, start: f64) -> Result<f64, SolveError> {
+        let failure = |iterations| SolveError::NoConvergence { start, iterations };
+        let mut x = start;
+        let mut fx = self.value(x);
+        let mut previous: Option<(f64, f64)> = None;
+        for iteration in 0..MAX_ITERATIONS {
+            if !fx.is_finite() {
+                return Err(failure(iteration));
+            }
+            if fx == 0.0 {
+                return Ok(x);
+            }
+            let h = 1e-7 * x.abs().max(1.0);
+            let slope = (self.value(x + h) - self.value(x - h)) / (2.0 * h);
+            let step = if slope != 0.0 && slope.is_finite() {
+                fx / slope
+            } else {
+                match previous {
+                    Some((px, pfx)) if pfx != fx => fx * (x - px) / (fx - pfx),
+                    _ => return Err(failure(iteration + 1)),
+                }
+            };
+            previous = Some((x, fx));
+            x -= step;
+            fx = self.value(x);
+            // Keep going until the steps stop shrinking x, so roots where the curve only touches
+            // zero, which Newton's method approaches slowly, are still found accurately.
+            if step.abs() < 1e-12 * x.abs().max(1.0) {
+                if fx.abs() < TOLERANCE {
+                    return Ok(x);
+                }
+                return Err(failure(iteration + 1));
+            }
+        }
+        return Err(failure(MAX_ITERATIONS));
+    }
+
+    /// Samples the range for sign changes and narrows each one down to a root. An equation
+    /// that is zero at every sample is an identity rather than one with thousands of roots.
+    fn scan(&self, low: f64, high: f64) -> Result<Vec<f64>, SolveError> {
+        let mut roots = Vec::<f64>::new();
+        let width = (high - low) / SEARCH_STEPS as f64;
+        let mut a = low;
+        let mut fa = self.value(a);
+        let mut everywhere = true;
+        for step in 1..SEARCH_STEPS + 1 {
+            let b = low + width * step as f64;
+            let fb = self.value(b);
+            if fa.is_finite() && fa.abs() < TOLERANCE {
+                push_root(&mut roots, a);
+            } else {
+                everywhere = false;
+                if fa.is_finite() && fb.is_finite() && fa.signum() != fb.signum() {
+                    if let Some(root) = self.bisect(a, fa, b) {
+                        push_root(&mut roots, root);
+                    }
+                }
+            }
+            a = b;
+            fa = fb;
+        }
+        if fa.is_finite() && fa.abs() < TOLERANCE {
+            push_root(&mut roots, a);
+        } else {
+            everywhere = false;
+        }
+        if everywhere {
+            return Err(SolveError::Identity { variable: String::from(self.variable) });
+        }
+        return Ok(roots);
+    }
+
+    /// Bisection on a bracketing interval. Sign changes across a pole, e.g. 1/x at 0, do not
+    /// settle near zero and are dropped.
+    fn bisect(&self, mut a: f64, mut fa: f64, mut b: f64) -> Option<f64> {
+        for _ in 0..MAX_ITERATIONS {
+            let middle = (a + b) / 2.0;
+            let fm = self.value(middle);
+            if !fm.is_finite() {
+                return None;
+            }
+            if fm == 0.0 || (b - a).abs() < 1e-15 * middle.abs().max(1.0) {
+                a = middle;
+                fa = fm;
+                break;
+            }
+            if fm.signum() == fa.signum() {
+                a = middle;
+                fa = fm;
+            } else {
+                b = middle;
+            }
+        }
+        if fa.abs() < TOLERANCE.sqrt() {
+            return Some(a);
+        }
+        return None;
+    }
+}
+
+/// Private function that turns left = right into the cells of left - right.
+fn difference(equation: &Expression) -> Vec<Cell> {
+    match *equation {
+        Expression::Operator(ref op, ref operands) if op == "=" => {
+            return expression::to_cells(&Expression::Operator(String::from("-"), operands.clone()));
+        }
+        _ => return expression::to_cells(equation),
+    }
+}
+
+fn push_root(roots: &mut Vec<f64>, root: f64) {
+    if roots.last().is_some_and(|last| (root - last).abs() < 1e-7 * root.abs().max(1.0)) {
+        return;
+    }
+    roots.push(root);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prefix;
+
+    fn parse(input: &str) -> Expression {
+        return expression::from_prefix(&prefix::translate_infix(input).unwrap()).unwrap();
+    }
+
+    fn roots(input: &str, guess: Option<f64>) -> Vec<f64> {
+        return solve(&parse(input), "x", guess, &HashMap::<String, f64>::new()).unwrap();
+    }
+
+    fn assert_close(expected: &[f64], actual: &[f64]) {
+        assert_eq!(expected.len(), actual.len(), "{:?}", actual);
+        for (expected, actual) in expected.iter().zip(actual.iter()) {
+            assert!((expected - actual).abs() < 1e-7, "{:?}", actual);
+        }
+    }
+
+    #[test]
+    fn test_multiple_roots() {
+        let root = 2.0_f64.sqrt();
+        assert_close(&[-root, root], &roots("x^2 - 2 = 0", None));
+        assert_close(&[1.0, 2.0, 3.0], &roots("(x - 1)(x - 2)(x - 3)", None));
+    }
+
+    #[test]
+    fn test_guess() {
+        let root = 2.0_f64.sqrt();
+        assert_close(&[-root], &roots("x^2 = 2", Some(-1.0)));
+        assert_close(&[root], &roots("x^2 = 2", Some(5.0)));
+        assert_close(&[std::f64::consts::PI], &roots("sin(x) = 0", Some(3.0)));
+    }
+
+    #[test]
+    fn test_touching_root() {
+        assert_close(&[0.0], &roots("x^2 = 0", None));
+        assert_close(&[3.0], &roots("(x - 3)^2", Some(10.0)));
+    }
+
+    #[test]
+    fn test_other_variables() {
+        let mut map = HashMap::<String, f64>::new();
+        map.insert(String::from("a"), 10.0);
+        map.insert(String::from("x"), 99.0);
+        let result = solve(&parse("2x + a = 0"), "x", None, &map).unwrap();
+        assert_close(&[-5.0], &result);
+    }
+
+    #[test]
+    fn test_pole_is_not_a_root() {
+        let result = solve(&parse("1 / x"), "x", None, &HashMap::<String, f64>::new());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let map = HashMap::<String, f64>::new();
+        assert_eq!(
+            Err(SolveError::Invalid(String::from("Variable b does not have a defined value."))),
+            solve(&parse("x + b = 0"), "x", None, &map)
+        );
+        assert_eq!(
+            Err(SolveError::NoRoot { low: -100.0, high: 100.0 }),
+            solve(&parse("x^2 + 1 = 0"), "x", None, &map)
+        );
+        match solve(&parse("x^2 + 1 = 0"), "x", Some(1.0), &map) {
+            Err(SolveError::NoConvergence { start, .. }) => assert_eq!(1.0, start),
+            result => panic!("{:?}", result),
+        }
+        assert!(solve_call(&[parse("x = 1"), Expression::Value(2.0)], &map).is_err());
+        assert_eq!(Err(SolveError::Identity { variable: String::from("x") }), solve(&parse("x = x"), "x", None, &map));
+        assert_eq!(
+            "The equation is true for every x.",
+            solve(&parse("sin(x)^2 + cos(x)^2 = 1"), "x", None, &map).unwrap_err().message()
+        );
+        assert_eq!(Ok(vec![0.0]), solve(&parse("x * x = 0"), "x", None, &map));
+    }
+
+    fn system(equations: &str, variables: &str) -> Result<Vec<(String, f64)>, SolveError> {
+        let args = [parse(equations), parse(variables)];
+        return solve_system_call(&args, &HashMap::<String, f64>::new());
+    }
+
+    #[test]
+    fn test_linear_system() {
+        let result = system("[2x + y = 5, x - y = 1]", "[x, y]").unwrap();
+        assert_eq!(vec!["x", "y"], result.iter().map(|answer| answer.0.as_str()).collect::<Vec<&str>>());
+        assert_close(&[2.0, 1.0], &result.iter().map(|answer| answer.1).collect::<Vec<f64>>());
+
+        let result = system("[a + b + c = 6, 2a - b = 0, c = 3a]", "[a, b, c]").unwrap();
+        assert_close(&[1.0, 2.0, 3.0], &result.iter().map(|answer| answer.1).collect::<Vec<f64>>());
+    }
+
+    #[test]
+    fn test_linear_system_uses_variables() {
+        let mut map = HashMap::<String, f64>::new();
+        map.insert(String::from("k"), 4.0);
+        map.insert(String::from("x"), 100.0);
+        let result = solve_system(&[parse("k x = 2")], &[String::from("x")], &map).unwrap();
+        assert_close(&[0.5], &result);
+    }
+
+    #[test]
+    fn test_linear_system_errors() {
+        assert_eq!(Err(SolveError::Inconsistent), system("[x + y = 1, 2x + 2y = 3]", "[x, y]"));
+        assert_eq!(
+            Err(SolveError::Underdetermined { free: vec![String::from("y")] }),
+            system("[x + y = 1, 2x + 2y = 2]", "[x, y]")
+        );
+        assert_eq!(
+            Err(SolveError::Invalid(String::from("Equation 1 is not linear in x, y."))),
+            system("[x y = 1, x - y = 0]", "[x, y]")
+        );
+        assert!(system("[x = 1, y = 2]", "[x, x]").is_err());
+    }
+
+    #[test]
+    fn test_solve_call() {
+        let map = HashMap::<String, f64>::new();
+        let call = parse("solve(x^3 = 10, x, 1 + 1)");
+        match call {
+            Expression::Function(_, ref args) => {
+                assert_close(&[10.0_f64.cbrt()], &solve_call(args, &map).unwrap());
+            }
+            _ => panic!("{:?}", call),
+        }
+    }
+}
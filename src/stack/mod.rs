@@ -0,0 +1,204 @@
+#![allow(clippy::needless_return)]
+
+use calculator;
+use calculator::Cell;
+use std::collections::HashMap;
+
+/// Struct that holds the value stack of the interactive reverse Polish (stack machine) mode.
+/// Every entry is applied as a whole; the stack as it was before each entry is kept so that it
+/// can be undone.
+#[derive(Debug, Clone, Default)]
+pub struct Stack {
+    values: Vec<f64>,
+    undo: Vec<Vec<f64>>,
+}
+
+impl Stack {
+    pub fn new() -> Stack {
+        return Stack {
+            values: Vec::<f64>::new(),
+            undo: Vec::<Vec<f64>>::new(),
+        };
+    }
+
+    /// Function that returns the values on the stack, bottom first.
+    pub fn values(&self) -> &[f64] {
+        return &self.values;
+    }
+
+    /// Function that returns the value on top of the stack, if there is one.
+    pub fn top(&self) -> Option<f64> {
+        return self.values.last().cloned();
+    }
+
+    /// Function that applies one entry to the stack. An entry is a whitespace separated list of
+    /// values, variables, operators [+, -, *, /, ^] and commands [dup, swap, drop, roll, clear, undo].
+    /// If any part of the entry fails the stack is left as it was before the entry.
+    pub fn execute(&mut self, entry: &str, map: &HashMap<String, f64>) -> Result<(), String> {
+        let before = self.values.clone();
+        // Undo works on a copy of the history, kept only when the whole entry succeeds.
+        let mut undo = self.undo.clone();
+        for item in entry.split_whitespace() {
+            if item == "undo" {
+                match undo.pop() {
+                    Some(values) => self.values = values,
+                    None => {
+                        self.values = before;
+                        return Err(String::from("Nothing to undo."));
+                    }
+                }
+                continue;
+            }
+            let result = self.apply(item, map);
+            if result.is_err() {
+                self.values = before;
+                return result;
+            }
+        }
+        if entry.split_whitespace().all(|item| item != "undo") {
+            undo.push(before);
+        }
+        self.undo = undo;
+        return Ok(());
+    }
+
+    /// Private function that applies a single value, variable, operator or command.
+    fn apply(&mut self, item: &str, map: &HashMap<String, f64>) -> Result<(), String> {
+        match item {
+            "+" | "-" | "*" | "/" | "^" => {
+                self.require(2, item)?;
+                let right = self.values.pop().unwrap();
+                let left = self.values.pop().unwrap();
+                match calculator::process(&String::from(item), &left, &right)? {
+                    Cell::Value(val) => self.values.push(val),
+                    _ => return Err(format!("Cannot process: ({}{}{})", item, left, right)),
+                }
+            }
+            "dup" => {
+                self.require(1, item)?;
+                let top = *self.values.last().unwrap();
+                self.values.push(top);
+            }
+            "swap" => {
+                self.require(2, item)?;
+                let len = self.values.len();
+                self.values.swap(len - 1, len - 2);
+            }
+            "drop" => {
+                self.require(1, item)?;
+                self.values.pop();
+            }
+            "roll" => {
+                self.require(1, item)?;
+                let top = self.values.pop().unwrap();
+                self.values.insert(0, top);
+            }
+            "clear" => {
+                self.values.clear();
+            }
+            _ => match item.parse::<f64>() {
+                Ok(val) => self.values.push(val),
+                Err(_) => match map.get(item).cloned().or_else(|| calculator::number_constant(item)) {
+                    Some(val) => self.values.push(val),
+                    None => return Err(format!("Variable {} does not have a defined value.", item)),
+                },
+            },
+        }
+        return Ok(());
+    }
+
+    fn require(&self, count: usize, item: &str) -> Result<(), String> {
+        if self.values.len() < count {
+            return Err(format!("Not enough values on the stack for {}.", item));
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(stack: &mut Stack, entry: &str) -> Result<(), String> {
+        return stack.execute(entry, &HashMap::<String, f64>::new());
+    }
+
+    #[test]
+    fn test_push_and_add() {
+        let mut stack = Stack::new();
+        run(&mut stack, "2").unwrap();
+        run(&mut stack, "3").unwrap();
+        run(&mut stack, "+").unwrap();
+        assert_eq!(&[5.0], stack.values());
+    }
+
+    #[test]
+    fn test_operand_order() {
+        let mut stack = Stack::new();
+        run(&mut stack, "10 4 -").unwrap();
+        assert_eq!(Some(6.0), stack.top());
+        run(&mut stack, "3 /").unwrap();
+        assert_eq!(Some(2.0), stack.top());
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut stack = Stack::new();
+        run(&mut stack, "1 2 3").unwrap();
+        run(&mut stack, "swap").unwrap();
+        assert_eq!(&[1.0, 3.0, 2.0], stack.values());
+        run(&mut stack, "dup").unwrap();
+        assert_eq!(&[1.0, 3.0, 2.0, 2.0], stack.values());
+        run(&mut stack, "drop roll").unwrap();
+        assert_eq!(&[2.0, 1.0, 3.0], stack.values());
+        run(&mut stack, "clear").unwrap();
+        assert!(stack.values().is_empty());
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut stack = Stack::new();
+        run(&mut stack, "1 2").unwrap();
+        run(&mut stack, "+").unwrap();
+        run(&mut stack, "undo").unwrap();
+        assert_eq!(&[1.0, 2.0], stack.values());
+        run(&mut stack, "undo").unwrap();
+        assert!(stack.values().is_empty());
+        assert!(run(&mut stack, "undo").is_err());
+    }
+
+    #[test]
+    fn test_error_leaves_stack_unchanged() {
+        let mut stack = Stack::new();
+        run(&mut stack, "1").unwrap();
+        let result = run(&mut stack, "2 + +");
+        assert_eq!(Err(String::from("Not enough values on the stack for +.")), result);
+        assert_eq!(&[1.0], stack.values());
+    }
+
+    #[test]
+    fn test_failed_undo_keeps_history() {
+        let mut stack = Stack::new();
+        run(&mut stack, "1").unwrap();
+        run(&mut stack, "2").unwrap();
+        assert!(run(&mut stack, "undo + +").is_err());
+        assert_eq!(&[1.0, 2.0], stack.values());
+        run(&mut stack, "undo").unwrap();
+        assert_eq!(&[1.0], stack.values());
+        assert!(run(&mut stack, "undo undo undo").is_err());
+        assert_eq!(&[1.0], stack.values());
+        run(&mut stack, "undo").unwrap();
+        assert!(stack.values().is_empty());
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut stack = Stack::new();
+        let mut map = HashMap::<String, f64>::new();
+        map.insert(String::from("a"), 4.0);
+        stack.execute("a a *", &map).unwrap();
+        assert_eq!(Some(16.0), stack.top());
+        let result = stack.execute("b", &map);
+        assert_eq!(Err(String::from("Variable b does not have a defined value.")), result);
+    }
+}
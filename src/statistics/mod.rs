@@ -0,0 +1,162 @@
+#![allow(clippy::needless_return)]
+
+/// Function that reports whether a built-in function summarises a list of numbers. These take
+/// any mix of numbers and lists, e.g. mean(1, 2, 3) or mean([1, 2, 3]) or mean(data).
+pub fn is_statistic(name: &str) -> bool {
+    match name {
+        "mean" | "median" | "mode" | "stdev" | "pstdev" | "var" | "pvar" | "percentile" | "sum" | "count"
+        | "min" | "max" => return true,
+        _ => return false,
+    }
+}
+
+/// Function that applies a statistics function to a list of numbers. For percentile the last
+/// number is the percentile wanted, from 0 to 100.
+/// # Example
+/// median(3, 1, 4, 1, 5) -> 3
+/// percentile(1, 2, 3, 4, 50) -> 2.5
+/// ...
+pub fn process_statistic(name: &str, values: &[f64]) -> Result<f64, String> {
+    if values.is_empty() {
+        return Err(format!("Cannot find the {} of an empty list.", name));
+    }
+    if values.iter().any(|val| val.is_nan()) {
+        return Err(format!("Function {} cannot take a value that is not a number.", name));
+    }
+    match name {
+        "sum" => return Ok(values.iter().sum()),
+        "count" => return Ok(values.len() as f64),
+        "min" => return Ok(values.iter().cloned().fold(f64::INFINITY, f64::min)),
+        "max" => return Ok(values.iter().cloned().fold(f64::NEG_INFINITY, f64::max)),
+        "mean" => return Ok(mean(values)),
+        "median" => return Ok(percentile(values, 50.0)),
+        "mode" => return mode(values),
+        "var" => return variance(values, 1.0),
+        "pvar" => return variance(values, 0.0),
+        "stdev" => return variance(values, 1.0).map(f64::sqrt),
+        "pstdev" => return variance(values, 0.0).map(f64::sqrt),
+        "percentile" => {
+            let (wanted, values) = values.split_last().unwrap();
+            if values.is_empty() {
+                return Err(String::from("Function percentile takes a list and a percentile, e.g. percentile(data, 90)."));
+            }
+            if *wanted < 0.0 || *wanted > 100.0 {
+                return Err(format!("Percentile {} must be from 0 to 100.", wanted));
+            }
+            return Ok(percentile(values, *wanted));
+        }
+        _ => return Err(format!("Unknown function {}.", name)),
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    return values.iter().sum::<f64>() / values.len() as f64;
+}
+
+/// Private function that finds a percentile by interpolating between the two nearest values,
+/// as spreadsheets do for PERCENTILE.INC.
+fn percentile(values: &[f64], wanted: f64) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let rank = wanted / 100.0 * (sorted.len() - 1) as f64;
+    let below = rank.floor() as usize;
+    let above = rank.ceil() as usize;
+    return sorted[below] + (rank - below as f64) * (sorted[above] - sorted[below]);
+}
+
+/// Private function that finds the most common value, taking the smallest when several are
+/// equally common.
+fn mode(values: &[f64]) -> Result<f64, String> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mut best = (sorted[0], 0);
+    let mut index = 0;
+    while index < sorted.len() {
+        let run = sorted[index..].iter().take_while(|val| **val == sorted[index]).count();
+        if run > best.1 {
+            best = (sorted[index], run);
+        }
+        index += run;
+    }
+    if best.1 < 2 && sorted.len() > 1 {
+        return Err(String::from("No mode: every value appears only once."));
+    }
+    return Ok(best.0);
+}
+
+/// Private function that finds the variance; `correction` is 1 for a sample and 0 for a whole
+/// population.
+fn variance(values: &[f64], correction: f64) -> Result<f64, String> {
+    if values.len() as f64 - correction < 1.0 {
+        return Err(String::from("A sample variance needs at least two values."));
+    }
+    let average = mean(values);
+    let squares = values.iter().map(|val| (val - average).powi(2)).sum::<f64>();
+    return Ok(squares / (values.len() as f64 - correction));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(expected: f64, name: &str, values: &[f64]) {
+        let actual = process_statistic(name, values).unwrap();
+        assert!((expected - actual).abs() < 1e-12, "{} gave {}", name, actual);
+    }
+
+    #[test]
+    fn test_averages() {
+        check(3.0, "mean", &[1.0, 2.0, 3.0, 4.0, 5.0]);
+        check(3.0, "median", &[5.0, 1.0, 3.0]);
+        check(2.5, "median", &[4.0, 1.0, 3.0, 2.0]);
+        check(1.0, "mode", &[3.0, 1.0, 4.0, 1.0, 5.0]);
+        check(2.0, "mode", &[3.0, 2.0, 3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_spread() {
+        let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        check(4.0, "pvar", &values);
+        check(2.0, "pstdev", &values);
+        check(32.0 / 7.0, "var", &values);
+        check((32.0f64 / 7.0).sqrt(), "stdev", &values);
+    }
+
+    #[test]
+    fn test_percentile() {
+        check(2.5, "percentile", &[1.0, 2.0, 3.0, 4.0, 50.0]);
+        check(4.0, "percentile", &[4.0, 1.0, 3.0, 2.0, 100.0]);
+        check(1.3, "percentile", &[1.0, 2.0, 3.0, 4.0, 10.0]);
+    }
+
+    #[test]
+    fn test_counts() {
+        check(10.0, "sum", &[1.0, 2.0, 3.0, 4.0]);
+        check(4.0, "count", &[1.0, 2.0, 3.0, 4.0]);
+        check(-1.0, "min", &[3.0, -1.0, 2.0]);
+        check(3.0, "max", &[3.0, -1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(process_statistic("mode", &[1.0, 2.0, 3.0]).is_err());
+        assert!(process_statistic("stdev", &[1.0]).is_err());
+        assert!(process_statistic("percentile", &[1.0, 2.0, 101.0]).is_err());
+        assert!(process_statistic("percentile", &[50.0]).is_err());
+    }
+
+    #[test]
+    fn test_empty() {
+        for name in ["mean", "median", "mode", "stdev", "pstdev", "var", "pvar", "percentile", "sum", "count", "min", "max"].iter() {
+            assert_eq!(Err(format!("Cannot find the {} of an empty list.", name)), process_statistic(name, &[]));
+        }
+    }
+
+    #[test]
+    fn test_not_a_number() {
+        assert!(process_statistic("mode", &[f64::NAN, 1.0]).is_err());
+        assert!(process_statistic("median", &[f64::NAN, 1.0, 2.0]).is_err());
+        assert!(process_statistic("percentile", &[1.0, 2.0, f64::NAN]).is_err());
+        assert_eq!(Ok(f64::INFINITY), process_statistic("max", &[1.0, f64::INFINITY]));
+    }
+}
@@ -0,0 +1,226 @@
+#![allow(clippy::needless_return)]
+
+use date;
+
+/// Enum that defines the kinds of token an infix equation is made of:
+/// * A Number [e.g. 1, 2.5, 6.02e23]
+/// * An Identifier [A variable, constant or function name, e.g. x, pi, sin]
+/// * An Operator [+, -, *, /, ^, =, ==, !=, <, <=, >, >=, ?, :]
+/// * An Open or Close bracket [( ) [ ] { }]
+/// * A Comma [Separates function arguments]
+/// * A Date [A day with an optional time of day, e.g. 2026-10-18 or 2026-10-18T09:30]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Number,
+    Date,
+    Identifier,
+    Operator,
+    Open,
+    Close,
+    Comma,
+}
+
+/// Struct that holds one token together with the span of characters it came from.
+/// `start` and `end` count characters from the start of the input; `end` is exclusive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Struct that describes what is wrong with an equation and the span of characters it concerns,
+/// counted as for `Token`. An error at the end of the equation has an empty span there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalcError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl CalcError {
+    pub fn new(message: String, start: usize, end: usize) -> CalcError {
+        return CalcError { message, start, end };
+    }
+}
+
+/// Function that splits an infix notated equation into tokens.
+/// # Example
+/// 2pi(r + 1) -> 2, pi, (, r, +, 1, )
+/// ...
+pub fn tokenize(input: &str) -> Result<Vec<Token>, CalcError> {
+    let chars = input.chars().collect::<Vec<char>>();
+    let mut ret = Vec::<Token>::new();
+    let mut position = 0;
+    while position < chars.len() {
+        let item = chars[position];
+        let start = position;
+        let kind;
+        if item.is_whitespace() {
+            position += 1;
+            continue;
+        } else if let Some(end) = date_end(&chars, position) {
+            kind = TokenKind::Date;
+            position = end;
+            let text = chars[start..end].iter().collect::<String>();
+            if date::parse_date(&text).is_none() {
+                return Err(CalcError::new(format!("Invalid date {} at position {}.", text, start + 1), start, end));
+            }
+        } else if item.is_ascii_digit() || (item == '.' && next_is_digit(&chars, position)) {
+            kind = TokenKind::Number;
+            while position < chars.len() && chars[position].is_ascii_digit() {
+                position += 1;
+            }
+            if position < chars.len() && chars[position] == '.' {
+                position += 1;
+                while position < chars.len() && chars[position].is_ascii_digit() {
+                    position += 1;
+                }
+            }
+            if position < chars.len() && (chars[position] == 'e' || chars[position] == 'E') {
+                let mut exponent = position + 1;
+                if exponent < chars.len() && (chars[exponent] == '+' || chars[exponent] == '-') {
+                    exponent += 1;
+                }
+                if exponent < chars.len() && chars[exponent].is_ascii_digit() {
+                    position = exponent;
+                    while position < chars.len() && chars[position].is_ascii_digit() {
+                        position += 1;
+                    }
+                }
+            }
+        } else if item.is_alphabetic() || item == '_' {
+            kind = TokenKind::Identifier;
+            while position < chars.len() && (chars[position].is_alphanumeric() || chars[position] == '_') {
+                position += 1;
+            }
+        } else if is_comparison_start(item) {
+            kind = TokenKind::Operator;
+            position += 1;
+            let doubled = position < chars.len() && chars[position] == '=';
+            if doubled {
+                position += 1;
+            } else if item == '!' {
+                let message = format!("Unexpected character '!' at position {}; did you mean '!='?", start + 1);
+                return Err(CalcError::new(message, start, position));
+            }
+        } else {
+            kind = match item {
+                '+' | '-' | '*' | '/' | '^' | '?' | ':' => TokenKind::Operator,
+                '(' | '[' | '{' => TokenKind::Open,
+                ')' | ']' | '}' => TokenKind::Close,
+                ',' => TokenKind::Comma,
+                _ => {
+                    let message = format!("Unexpected character '{}' at position {}.", item, position + 1);
+                    return Err(CalcError::new(message, start, start + 1));
+                }
+            };
+            position += 1;
+        }
+        ret.push(Token {
+            kind,
+            text: chars[start..position].iter().collect(),
+            start,
+            end: position,
+        });
+    }
+    return Ok(ret);
+}
+
+/// Private function that reports whether a character starts an operator that may be followed by
+/// '=', e.g. '<' of '<='. A lone '=' is assignment and a lone '!' is an error.
+fn is_comparison_start(item: char) -> bool {
+    match item {
+        '=' | '!' | '<' | '>' => return true,
+        _ => return false,
+    }
+}
+
+/// Private function that finds the end of a date literal starting at `position`, i.e. digits in
+/// the pattern 2026-10-18, optionally followed by T09:30 or T09:30:45.
+fn date_end(chars: &[char], position: usize) -> Option<usize> {
+    let matches = |pattern: &str, from: usize| -> bool {
+        return pattern.chars().enumerate().all(|(offset, expected)| match chars.get(from + offset) {
+            Some(actual) if expected == '9' => actual.is_ascii_digit(),
+            Some(actual) => *actual == expected,
+            None => false,
+        });
+    };
+    if !matches("9999-99-99", position) {
+        return None;
+    }
+    let mut ret = position + 10;
+    if matches("T99:99", ret) {
+        ret += 6;
+        if matches(":99", ret) {
+            ret += 3;
+        }
+    }
+    if chars.get(ret).is_some_and(|next| next.is_alphanumeric() || *next == '.') {
+        return None;
+    }
+    return Some(ret);
+}
+
+fn next_is_digit(chars: &[char], position: usize) -> bool {
+    return position + 1 < chars.len() && chars[position + 1].is_ascii_digit();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(input: &str) -> Vec<String> {
+        return tokenize(input).unwrap().into_iter().map(|token| token.text).collect();
+    }
+
+    #[test]
+    fn test_simple() {
+        assert_eq!(vec!["a", "+", "2"], texts("a + 2"));
+        assert_eq!(vec!["(", "a", "+", "b", ")", "*", "c"], texts("(a+b)*c"));
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(vec!["3.14", "+", ".5", "*", "6.02e23", "-", "1E-3"], texts("3.14 + .5 * 6.02e23 - 1E-3"));
+    }
+
+    #[test]
+    fn test_juxtaposition() {
+        assert_eq!(vec!["2", "pi"], texts("2pi"));
+        assert_eq!(vec!["2", "e"], texts("2e"));
+        assert_eq!(vec!["x2", "y"], texts("x2 y"));
+    }
+
+    #[test]
+    fn test_spans() {
+        let tokens = tokenize("π + 10").unwrap();
+        assert_eq!(TokenKind::Identifier, tokens[0].kind);
+        assert_eq!((0, 1), (tokens[0].start, tokens[0].end));
+        assert_eq!((4, 6), (tokens[2].start, tokens[2].end));
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(vec!["2026-12-01", "-", "2026-10-18T09:30", "in", "days"], texts("2026-12-01 - 2026-10-18T09:30 in days"));
+        assert_eq!(TokenKind::Date, tokenize("2026-10-18T09:30:45").unwrap()[0].kind);
+        assert_eq!(vec!["2026", "-", "10", "-", "1"], texts("2026-10-1"));
+        assert_eq!(
+            Err(CalcError::new(String::from("Invalid date 2026-02-30 at position 3."), 2, 12)),
+            tokenize("1 2026-02-30").map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(vec!["a", "==", "b", "!=", "c", "<=", "d", "<", "e"], texts("a == b != c <= d < e"));
+        assert_eq!(vec!["x", "=", "c", "?", "1", ":", "2"], texts("x = c ? 1 : 2"));
+        assert!(tokenize("a ! b").is_err());
+    }
+
+    #[test]
+    fn test_unexpected_character() {
+        assert_eq!(Err(CalcError::new(String::from("Unexpected character '#' at position 3."), 2, 3)), tokenize("a #b"));
+    }
+}
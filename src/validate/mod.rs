@@ -0,0 +1,226 @@
+#![allow(clippy::needless_return)]
+
+/// Struct that describes the first bracket that keeps an equation from being balanced.
+/// `position` counts characters from the start of the input, starting at 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BracketError {
+    pub position: usize,
+    pub bracket: char,
+    pub message: String,
+}
+
+/// Function that checks that every '(', '[' and '{' in an equation is closed by the matching
+/// ')', ']' or '}', in order.
+/// # Example
+/// (a + b -> Unmatched '(' at position 1.
+/// [a + b) -> Mismatched ')' at position 7; expected ']' to close '[' at position 1.
+/// ...
+pub fn check_brackets(input: &str) -> Result<(), BracketError> {
+    let mut open = Vec::<(usize, char)>::new();
+    for (position, item) in input.chars().enumerate() {
+        match item {
+            '(' | '[' | '{' => open.push((position, item)),
+            ')' | ']' | '}' => match open.pop() {
+                Some((opened_at, opener)) => {
+                    if get_closer(opener) != item {
+                        return Err(BracketError {
+                            position,
+                            bracket: item,
+                            message: format!(
+                                "Mismatched '{}' at position {}; expected '{}' to close '{}' at position {}.",
+                                item,
+                                position + 1,
+                                get_closer(opener),
+                                opener,
+                                opened_at + 1
+                            ),
+                        });
+                    }
+                }
+                None => {
+                    return Err(BracketError {
+                        position,
+                        bracket: item,
+                        message: format!("Unmatched '{}' at position {}.", item, position + 1),
+                    });
+                }
+            },
+            _ => {}
+        }
+    }
+    match open.first() {
+        Some(&(position, item)) => {
+            return Err(BracketError {
+                position,
+                bracket: item,
+                message: format!("Unmatched '{}' at position {}.", item, position + 1),
+            });
+        }
+        None => return Ok(()),
+    }
+}
+
+/// Function that suggests a balanced version of an equation by closing every bracket that is
+/// left open, innermost first. Returns None when the input is already balanced or when it has a
+/// closing bracket that cannot be fixed by appending.
+/// # Example
+/// (a + [b * c -> (a + [b * c])
+/// ...
+pub fn auto_close(input: &str) -> Option<String> {
+    let mut open = Vec::<char>::new();
+    for item in input.chars() {
+        match item {
+            '(' | '[' | '{' => open.push(item),
+            ')' | ']' | '}' => match open.pop() {
+                Some(opener) if get_closer(opener) == item => {}
+                _ => return None,
+            },
+            _ => {}
+        }
+    }
+    if open.is_empty() {
+        return None;
+    }
+    let mut ret = String::from(input.trim_end());
+    while let Some(opener) = open.pop() {
+        ret.push(get_closer(opener));
+    }
+    return Some(ret);
+}
+
+/// Function that rewrites the alternative grouping brackets '[]' and '{}' as parentheses, which
+/// is all the translators understand.
+pub fn normalize_brackets(input: &str) -> String {
+    return input
+        .chars()
+        .map(|item| match item {
+            '[' | '{' => '(',
+            ']' | '}' => ')',
+            _ => item,
+        })
+        .collect();
+}
+
+fn get_closer(opener: char) -> char {
+    match opener {
+        '[' => return ']',
+        '{' => return '}',
+        _ => return ')',
+    }
+}
+
+/// Function that finds the bracket that matches one touching the caret, preferring the one just
+/// before it. Returns the positions of both brackets, in order, or None when there is no bracket
+/// there or it is unmatched. Positions count characters from the start of the input.
+/// # Example
+/// (a + [b]) with the caret at 9 -> (0, 8)
+/// (a + [b]) with the caret at 5 -> (5, 7)
+/// ...
+pub fn matching_bracket(input: &str, caret: usize) -> Option<(usize, usize)> {
+    let chars = input.chars().collect::<Vec<char>>();
+    let is_bracket = |position: usize| chars.get(position).is_some_and(|item| "([{)]}".contains(*item));
+    let position = if caret > 0 && is_bracket(caret - 1) {
+        caret - 1
+    } else if is_bracket(caret) {
+        caret
+    } else {
+        return None;
+    };
+    let item = chars[position];
+    let mut depth = 0;
+    if "([{".contains(item) {
+        for (index, other) in chars.iter().enumerate().skip(position) {
+            if "([{".contains(*other) {
+                depth += 1;
+            } else if ")]}".contains(*other) {
+                depth -= 1;
+                if depth == 0 {
+                    return if *other == get_closer(item) { Some((position, index)) } else { None };
+                }
+            }
+        }
+    } else {
+        for index in (0..=position).rev() {
+            let other = chars[index];
+            if ")]}".contains(other) {
+                depth += 1;
+            } else if "([{".contains(other) {
+                depth -= 1;
+                if depth == 0 {
+                    return if get_closer(other) == item { Some((index, position)) } else { None };
+                }
+            }
+        }
+    }
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balanced() {
+        assert_eq!(Ok(()), check_brackets("(a + b) * [c - {d / e}]"));
+        assert_eq!(Ok(()), check_brackets("a + b"));
+    }
+
+    #[test]
+    fn test_unmatched_opener() {
+        let result = check_brackets("(a + b");
+        assert_eq!(0, result.clone().err().unwrap().position);
+        assert_eq!("Unmatched '(' at position 1.", result.err().unwrap().message);
+
+        let result = check_brackets("(a + (b * c)");
+        assert_eq!(0, result.err().unwrap().position);
+    }
+
+    #[test]
+    fn test_unmatched_closer() {
+        let result = check_brackets("a + b) * c").err().unwrap();
+        assert_eq!(5, result.position);
+        assert_eq!(')', result.bracket);
+        assert_eq!("Unmatched ')' at position 6.", result.message);
+    }
+
+    #[test]
+    fn test_mismatched_closer() {
+        let result = check_brackets("[a + b) * c").err().unwrap();
+        assert_eq!(6, result.position);
+        assert_eq!(
+            "Mismatched ')' at position 7; expected ']' to close '[' at position 1.",
+            result.message
+        );
+    }
+
+    #[test]
+    fn test_position_counts_characters() {
+        let result = check_brackets("π + (2").err().unwrap();
+        assert_eq!(4, result.position);
+    }
+
+    #[test]
+    fn test_matching_bracket() {
+        assert_eq!(Some((0, 8)), matching_bracket("(a + [b])", 9));
+        assert_eq!(Some((5, 7)), matching_bracket("(a + [b])", 5));
+        assert_eq!(Some((0, 8)), matching_bracket("(a + [b])", 0));
+        assert_eq!(None, matching_bracket("(a + [b])", 3));
+        assert_eq!(None, matching_bracket("(a + b", 1));
+        assert_eq!(None, matching_bracket("[a + b)", 7));
+        assert_eq!(Some((2, 4)), matching_bracket("π (2)", 5));
+    }
+
+    #[test]
+    fn test_auto_close() {
+        assert_eq!(Some(String::from("(a + [b * c])")), auto_close("(a + [b * c"));
+        assert_eq!(Some(String::from("{(a + b)}")), auto_close("{(a + b "));
+        assert_eq!(None, auto_close("(a + b)"));
+        assert_eq!(None, auto_close("(a + b]"));
+        assert_eq!(None, auto_close("a + b)"));
+    }
+
+    #[test]
+    fn test_normalize_brackets() {
+        assert_eq!("((a + b) * (c))", normalize_brackets("{[a + b] * (c)}"));
+    }
+}
@@ -0,0 +1,819 @@
+#![allow(clippy::needless_return)]
+
+use calculator;
+use calculator::Cell;
+use date;
+use expression;
+use expression::Expression;
+use integrate;
+use integrate::Lookup;
+use linear;
+use statistics;
+use std::collections::HashMap;
+
+/// Largest power a matrix can be raised to.
+pub const MAX_MATRIX_POWER: f64 = 1_000_000_000.0;
+
+/// Enum that defines the answers an equation can have:
+/// * A Number [e.g. 1, 2.5]
+/// * A Vector [e.g. [1, 2, 3]]
+/// * A Matrix [A list of rows of equal length, e.g. [[1, 2], [3, 4]]]
+/// * A Boolean [The answer of a comparison, true or false]
+/// * A Date [Seconds since 1970-01-01 UTC, e.g. 2026-10-18]
+/// * A Duration [A length of time in seconds, e.g. 3 weeks]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Vector(Vec<f64>),
+    Matrix(Vec<Vec<f64>>),
+    Boolean(bool),
+    Date(f64),
+    Duration(f64),
+}
+
+impl Value {
+    /// Function that returns the value as a number, or an error naming what it is instead.
+    pub fn number(&self) -> Result<f64, String> {
+        match *self {
+            Value::Number(val) => return Ok(val),
+            _ => return Err(format!("Expected a number, not {}.", self.describe())),
+        }
+    }
+
+    /// Function that returns the value as a condition. A number counts as true unless it is 0.
+    pub fn truth(&self) -> Result<bool, String> {
+        match *self {
+            Value::Boolean(val) => return Ok(val),
+            Value::Number(val) => return Ok(val != 0.0),
+            _ => return Err(format!("Expected true or false, not {}.", self.describe())),
+        }
+    }
+
+    /// Function that describes the shape of the value in words, for error messages.
+    pub fn describe(&self) -> String {
+        match *self {
+            Value::Number(_) => return String::from("a number"),
+            Value::Vector(ref values) => return format!("a vector of length {}", values.len()),
+            Value::Matrix(ref rows) => return format!("a {} x {} matrix", rows.len(), rows[0].len()),
+            Value::Boolean(_) => return String::from("a boolean"),
+            Value::Date(_) => return String::from("a date"),
+            Value::Duration(_) => return String::from("a duration"),
+        }
+    }
+}
+
+/// Function that prints a value. Numbers on their own keep the calculator's eight decimal
+/// places; vectors and matrices are printed as nested brackets, booleans as true or false, and
+/// dates and durations as `date::print_date` and `date::print_duration` write them.
+/// # Example
+/// [[1, 2], [3, 4]]
+/// ...
+pub fn print_value(value: &Value) -> String {
+    return print_value_with(value, &date::Formats::default());
+}
+
+/// Function that prints a value as `print_value` does, but shows dates and durations in the
+/// given formats.
+pub fn print_value_with(value: &Value, formats: &date::Formats) -> String {
+    match *value {
+        Value::Number(val) => return format!("{:.8}", val),
+        Value::Boolean(val) => return format!("{}", val),
+        Value::Date(val) => return date::format_date(val, formats.date),
+        Value::Duration(val) => return date::format_duration(val, formats.duration),
+        Value::Vector(ref values) => return print_row(values),
+        Value::Matrix(ref rows) => {
+            let rows = rows.iter().map(|row| print_row(row)).collect::<Vec<String>>();
+            return format!("[{}]", rows.join(", "));
+        }
+    }
+}
+
+fn print_row(values: &[f64]) -> String {
+    let values = values.iter().map(|val| print_element(*val)).collect::<Vec<String>>();
+    return format!("[{}]", values.join(", "));
+}
+
+/// Private function that prints an element to ten decimal places without trailing zeros, so that
+/// rounding noise such as 1.9999999999999996 reads as 2.
+fn print_element(val: f64) -> String {
+    if !val.is_finite() {
+        return format!("{}", val);
+    }
+    let ret = format!("{:.10}", val);
+    let ret = ret.trim_end_matches('0').trim_end_matches('.');
+    if ret == "-0" {
+        return String::from("0");
+    }
+    return String::from(ret);
+}
+
+/// Function that reports whether an equation needs to be evaluated as a tree rather than by
+/// `calculator::calculate`: it builds vectors, uses matrix functions, binds a variable or
+/// works with true and false, dates or durations.
+pub fn needs_tree(vec: &[Cell]) -> bool {
+    return vec.iter().any(|cell| match *cell {
+        Cell::Function(ref name, _) => {
+            is_array_function(name) || integrate::is_binding(name) || is_logical(name)
+        }
+        Cell::Operator(ref op) => is_logical(op) || op == "in",
+        Cell::Variable(ref name) => is_constant(name),
+        _ => false,
+    });
+}
+
+/// Function that reports whether a name stands for a value of its own unless it is set as a
+/// variable: pi, e, true, false, now, a date literal or a unit of time.
+pub fn is_constant(name: &str) -> bool {
+    match name {
+        "pi" | "e" | "true" | "false" | "now" => return true,
+        name => return date::is_date_literal(name) || date::unit_seconds(name).is_some(),
+    }
+}
+
+/// Function that reports whether an operator or built-in function works with true and false.
+pub fn is_logical(name: &str) -> bool {
+    match name {
+        "and" | "or" | "not" | "if" => return true,
+        name => return calculator::is_comparison(name),
+    }
+}
+
+/// Function that reports whether a built-in function works on vectors and matrices.
+pub fn is_array_function(name: &str) -> bool {
+    match name {
+        "list" | "dot" | "cross" | "transpose" | "det" | "inv" | "index" => return true,
+        _ => return false,
+    }
+}
+
+/// Function that resolves an equation to a value, using the passed in hash map to resolve variables.
+pub fn calculate(vec: &Vec<Cell>, map: &HashMap<String, f64>) -> Result<Value, String> {
+    if !needs_tree(vec) {
+        match calculator::calculate(vec, map)?.pop() {
+            Some(Cell::Value(val)) => return Ok(Value::Number(val)),
+            _ => return Err(String::from("Equation did not give a value.")),
+        }
+    }
+    let expression = expression::from_cells(vec)?;
+    let mut budget = integrate::MAX_EVALUATIONS;
+    return evaluate(&expression, map, &mut budget);
+}
+
+/// Function that resolves an equation to a value like `calculate`, also resolving the names of
+/// lists, e.g. mean(data), and the current time.
+pub fn calculate_with(vec: &Vec<Cell>, variables: &integrate::Variables) -> Result<Value, String> {
+    let names_list = |cell: &Cell| matches!(*cell, Cell::Variable(ref name) if variables.lists.contains_key(name));
+    if !needs_tree(vec) && !vec.iter().any(names_list) {
+        return calculate(vec, variables.numbers);
+    }
+    let expression = expression::from_cells(vec)?;
+    let mut budget = integrate::MAX_EVALUATIONS;
+    return evaluate(&expression, variables, &mut budget);
+}
+
+/// Function that evaluates an equation tree, counting the evaluations made by integrate, sum
+/// and product against the budget.
+pub fn evaluate(expression: &Expression, variables: &dyn Lookup, budget: &mut usize) -> Result<Value, String> {
+    match *expression {
+        Expression::Value(val) => {
+            return Ok(Value::Number(val));
+        }
+        Expression::Variable(ref name) => match variables.lookup(name) {
+            Some(val) => return Ok(Value::Number(val)),
+            None => match variables.lookup_list(name) {
+                Some(values) => return Ok(Value::Vector(values)),
+                None => return constant(name, variables),
+            },
+        },
+        // if, and and or only evaluate the arguments they need, so a branch that is not taken
+        // may divide by zero or name an undefined variable.
+        Expression::Function(ref name, ref args) if name == "if" && args.len() == 3 => {
+            if evaluate(&args[0], variables, budget)?.truth()? {
+                return evaluate(&args[1], variables, budget);
+            }
+            return evaluate(&args[2], variables, budget);
+        }
+        Expression::Operator(ref op, ref args) if op == "and" || op == "or" => {
+            for arg in args.iter() {
+                if evaluate(arg, variables, budget)?.truth()? != (op == "and") {
+                    return Ok(Value::Boolean(op == "or"));
+                }
+            }
+            return Ok(Value::Boolean(op == "and"));
+        }
+        Expression::Function(ref name, ref args) if integrate::binds(name, args) => {
+            return Ok(Value::Number(integrate::evaluate_binding(name, args, variables, budget)?));
+        }
+        Expression::Function(ref name, ref args) if name == "list" => {
+            let mut items = Vec::<Value>::new();
+            for arg in args.iter() {
+                items.push(evaluate(arg, variables, budget)?);
+            }
+            return build(items);
+        }
+        Expression::Operator(ref name, ref args) | Expression::Function(ref name, ref args) => {
+            let mut values = Vec::<Value>::new();
+            for arg in args.iter() {
+                values.push(evaluate(arg, variables, budget)?);
+            }
+            return apply(name, values);
+        }
+    }
+}
+
+/// Private function that gives the value of a name that is not set as a variable.
+fn constant(name: &str, variables: &dyn Lookup) -> Result<Value, String> {
+    match name {
+        "true" | "false" => return Ok(Value::Boolean(name == "true")),
+        "now" => match variables.now() {
+            Some(seconds) => return Ok(Value::Date(seconds)),
+            None => return Err(String::from("The current time is not available.")),
+        },
+        _ => {}
+    }
+    if let Some(number) = calculator::number_constant(name) {
+        return Ok(Value::Number(number));
+    }
+    if let Some(seconds) = date::parse_date(name) {
+        return Ok(Value::Date(seconds));
+    }
+    if let Some(seconds) = date::unit_seconds(name) {
+        return Ok(Value::Duration(seconds));
+    }
+    return Err(format!("Variable {} does not have a defined value.", name));
+}
+
+/// Private function that turns the items of a list into a vector (all numbers) or a matrix (all
+/// vectors of one length).
+fn build(items: Vec<Value>) -> Result<Value, String> {
+    if items.iter().all(|item| matches!(*item, Value::Number(_))) {
+        return Ok(Value::Vector(items.iter().map(|item| item.number().unwrap()).collect()));
+    }
+    let mut rows = Vec::<Vec<f64>>::new();
+    for item in items.into_iter() {
+        match item {
+            Value::Vector(row) => {
+                if rows.first().is_some_and(|first| first.len() != row.len()) {
+                    return Err(String::from("Every row of a matrix must have the same length."));
+                }
+                rows.push(row);
+            }
+            _ => return Err(format!("A matrix row must be a vector, not {}.", item.describe())),
+        }
+    }
+    return Ok(Value::Matrix(rows));
+}
+
+/// Function that applies an operator or a built-in function to values. Numbers are handed to
+/// `calculator::process_function`; vectors and matrices follow the rules of linear algebra.
+pub fn apply(name: &str, args: Vec<Value>) -> Result<Value, String> {
+    if is_logical(name) {
+        return apply_logical(name, args);
+    }
+    if name == "in" || args.iter().any(is_temporal) {
+        return apply_temporal(name, args);
+    }
+    if let Some(arg) = args.iter().find(|arg| matches!(**arg, Value::Boolean(_))) {
+        return Err(format!("Function {} needs numbers, not {}.", name, arg.describe()));
+    }
+    if !is_array_function(name) && args.iter().all(|arg| matches!(*arg, Value::Number(_))) {
+        let numbers = args.iter().map(|arg| arg.number().unwrap()).collect::<Vec<f64>>();
+        return Ok(Value::Number(calculator::process_function(name, &numbers)?));
+    }
+    calculator::check_arity(name, args.len())?;
+    if statistics::is_statistic(name) {
+        // Statistics read every number they are given, whether on its own or in a list.
+        let mut numbers = Vec::<f64>::new();
+        for arg in args.iter() {
+            match *arg {
+                Value::Number(val) => numbers.push(val),
+                Value::Vector(ref values) => numbers.extend(values.iter()),
+                Value::Matrix(ref rows) => numbers.extend(rows.iter().flat_map(|row| row.iter())),
+                Value::Boolean(_) | Value::Date(_) | Value::Duration(_) => unreachable!(),
+            }
+        }
+        return Ok(Value::Number(statistics::process_statistic(name, &numbers)?));
+    }
+    match name {
+        "+" | "-" | "*" | "/" | "^" if args.len() == 1 => {
+            let identity = if name == "-" { 0.0 } else { 1.0 };
+            return operate(name, Value::Number(identity), args.into_iter().next().unwrap());
+        }
+        "+" | "-" | "*" | "/" | "^" => {
+            let mut iter = args.into_iter();
+            let mut ret = iter.next().unwrap();
+            for arg in iter {
+                ret = operate(name, ret, arg)?;
+            }
+            return Ok(ret);
+        }
+        "dot" => match (&args[0], &args[1]) {
+            (Value::Vector(a), Value::Vector(b)) if a.len() == b.len() => {
+                return Ok(Value::Number(dot(a, b)));
+            }
+            _ => return Err(shape_error("dot", &args[0], &args[1])),
+        },
+        "cross" => match (&args[0], &args[1]) {
+            (Value::Vector(a), Value::Vector(b)) if a.len() == 3 && b.len() == 3 => {
+                return Ok(Value::Vector(vec![
+                    a[1] * b[2] - a[2] * b[1],
+                    a[2] * b[0] - a[0] * b[2],
+                    a[0] * b[1] - a[1] * b[0],
+                ]));
+            }
+            _ => return Err(String::from("Function cross needs two vectors of length 3.")),
+        },
+        "transpose" => match args[0] {
+            Value::Number(_) | Value::Boolean(_) | Value::Date(_) | Value::Duration(_) => return Ok(args[0].clone()),
+            Value::Vector(ref values) => return Ok(Value::Matrix(values.iter().map(|val| vec![*val]).collect())),
+            Value::Matrix(ref rows) => return Ok(Value::Matrix(transpose(rows))),
+        },
+        "det" => match args[0] {
+            Value::Number(val) => return Ok(Value::Number(val)),
+            Value::Matrix(ref rows) if rows.len() == rows[0].len() => {
+                return Ok(Value::Number(linear::determinant(rows)));
+            }
+            _ => return Err(format!("Function det needs a square matrix, not {}.", args[0].describe())),
+        },
+        "inv" => match args[0] {
+            Value::Number(val) => return Ok(Value::Number(1.0 / val)),
+            Value::Matrix(ref rows) if rows.len() == rows[0].len() => match linear::inverse(rows) {
+                Some(inverse) => return Ok(Value::Matrix(inverse)),
+                None => return Err(String::from("Matrix is singular, so it has no inverse.")),
+            },
+            _ => return Err(format!("Function inv needs a square matrix, not {}.", args[0].describe())),
+        },
+        "index" => {
+            let mut indexes = Vec::<usize>::new();
+            for arg in args[1..].iter() {
+                let position = arg.number()?;
+                if position.fract() != 0.0 || position < 1.0 {
+                    return Err(format!("Index {} must be a whole number from 1.", position));
+                }
+                indexes.push(position as usize - 1);
+            }
+            return index(&args[0], &indexes);
+        }
+        _ => {
+            // Functions of one number, e.g. sqrt, apply to each element.
+            if args.len() == 1 && calculator::get_function_arity(name) == (1, Some(1)) {
+                return map(&args[0], |val| calculator::process_function(name, &[val]));
+            }
+            let other = args.iter().find(|arg| !matches!(**arg, Value::Number(_))).unwrap();
+            return Err(format!("Function {} needs numbers, not {}.", name, other.describe()));
+        }
+    }
+}
+
+/// Private function that applies a comparison, not, or an if whose arguments are all known.
+/// Comparisons take two numbers, or two booleans for == and !=.
+fn apply_logical(name: &str, args: Vec<Value>) -> Result<Value, String> {
+    calculator::check_arity(name, args.len())?;
+    match (name, &args[..]) {
+        ("not", [arg]) => return Ok(Value::Boolean(!arg.truth()?)),
+        ("if", [condition, then, otherwise]) => {
+            if condition.truth()? {
+                return Ok(then.clone());
+            }
+            return Ok(otherwise.clone());
+        }
+        ("and", _) | ("or", _) => {
+            let truths = args.iter().map(Value::truth).collect::<Result<Vec<bool>, String>>()?;
+            if name == "and" {
+                return Ok(Value::Boolean(truths.iter().all(|val| *val)));
+            }
+            return Ok(Value::Boolean(truths.iter().any(|val| *val)));
+        }
+        ("==", [a, b]) if matches!((a, b), (Value::Boolean(_), Value::Boolean(_))) => {
+            return Ok(Value::Boolean(a == b));
+        }
+        ("!=", [a, b]) if matches!((a, b), (Value::Boolean(_), Value::Boolean(_))) => {
+            return Ok(Value::Boolean(a != b));
+        }
+        (op, [a, b]) if comparable(a, b).is_some() => {
+            let (a, b) = comparable(a, b).unwrap();
+            let ret = match op {
+                "==" => a == b,
+                "!=" => a != b,
+                "<" => a < b,
+                "<=" => a <= b,
+                ">" => a > b,
+                _ => a >= b,
+            };
+            return Ok(Value::Boolean(ret));
+        }
+        (op, [a, b]) => return Err(format!("Cannot compare {} with {} using {}.", a.describe(), b.describe(), op)),
+        _ => return Err(format!("Unknown function {}.", name)),
+    }
+}
+
+/// Private function that returns two values as numbers that can be compared: two numbers, two
+/// dates or two durations.
+fn comparable(a: &Value, b: &Value) -> Option<(f64, f64)> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) | (Value::Date(a), Value::Date(b)) | (Value::Duration(a), Value::Duration(b)) => {
+            return Some((*a, *b));
+        }
+        _ => return None,
+    }
+}
+
+fn is_temporal(value: &Value) -> bool {
+    return matches!(*value, Value::Date(_) | Value::Duration(_));
+}
+
+/// Private function that applies an operator to dates and durations, e.g. a date minus a date
+/// is a duration and a duration in a unit of time is a number.
+fn apply_temporal(name: &str, args: Vec<Value>) -> Result<Value, String> {
+    calculator::check_arity(name, args.len())?;
+    match (name, &args[..]) {
+        ("-", [Value::Duration(a)]) => return Ok(Value::Duration(-a)),
+        ("abs", [Value::Duration(a)]) => return Ok(Value::Duration(a.abs())),
+        ("+", _) | ("-", _) | ("*", _) | ("/", _) | ("in", _) if args.len() >= 2 => {
+            let mut iter = args.into_iter();
+            let mut ret = iter.next().unwrap();
+            for arg in iter {
+                ret = operate_temporal(name, ret, arg)?;
+            }
+            return Ok(ret);
+        }
+        _ => {
+            let other = args.iter().find(|arg| !matches!(**arg, Value::Number(_))).unwrap();
+            return Err(format!("Function {} needs numbers, not {}.", name, other.describe()));
+        }
+    }
+}
+
+fn operate_temporal(op: &str, left: Value, right: Value) -> Result<Value, String> {
+    match (op, &left, &right) {
+        ("+", &Value::Date(a), &Value::Duration(b)) | ("+", &Value::Duration(b), &Value::Date(a)) => {
+            return Ok(Value::Date(date::check_date(a + b)?));
+        }
+        ("-", &Value::Date(a), &Value::Duration(b)) => return Ok(Value::Date(date::check_date(a - b)?)),
+        ("-", &Value::Date(a), &Value::Date(b)) => return Ok(Value::Duration(a - b)),
+        ("+", &Value::Duration(a), &Value::Duration(b)) => return Ok(Value::Duration(a + b)),
+        ("-", &Value::Duration(a), &Value::Duration(b)) => return Ok(Value::Duration(a - b)),
+        ("*", &Value::Duration(a), &Value::Number(b)) | ("*", &Value::Number(b), &Value::Duration(a)) => {
+            return Ok(Value::Duration(a * b));
+        }
+        ("/", &Value::Duration(a), &Value::Number(b)) => return Ok(Value::Duration(a / b)),
+        ("/", &Value::Duration(a), &Value::Duration(b)) | ("in", &Value::Duration(a), &Value::Duration(b)) => {
+            return Ok(Value::Number(a / b));
+        }
+        ("in", _, _) => {
+            return Err(format!("Cannot convert {} with in; use it on a duration, e.g. 90 minutes in hours.", left.describe()));
+        }
+        _ => return Err(shape_error(op, &left, &right)),
+    }
+}
+
+/// Private function that applies a binary operator where at least one side may be a vector or
+/// a matrix.
+fn operate(op: &str, left: Value, right: Value) -> Result<Value, String> {
+    match (op, &left, &right) {
+        (_, &Value::Number(a), &Value::Number(b)) => {
+            return Ok(Value::Number(calculator::process_function(op, &[a, b])?));
+        }
+        ("*", Value::Matrix(a), Value::Matrix(b)) => {
+            if a[0].len() != b.len() {
+                return Err(shape_error(op, &left, &right));
+            }
+            let columns = transpose(b);
+            let rows = a
+                .iter()
+                .map(|row| columns.iter().map(|column| dot(row, column)).collect())
+                .collect();
+            return Ok(Value::Matrix(rows));
+        }
+        ("*", Value::Matrix(a), Value::Vector(b)) => {
+            if a[0].len() != b.len() {
+                return Err(shape_error(op, &left, &right));
+            }
+            return Ok(Value::Vector(a.iter().map(|row| dot(row, b)).collect()));
+        }
+        ("*", Value::Vector(a), Value::Matrix(b)) => {
+            if a.len() != b.len() {
+                return Err(shape_error(op, &left, &right));
+            }
+            return Ok(Value::Vector(transpose(b).iter().map(|column| dot(a, column)).collect()));
+        }
+        ("/", &Value::Matrix(_), &Value::Matrix(_)) => {
+            return Err(String::from("Cannot divide by a matrix; multiply by inv() instead."));
+        }
+        ("^", Value::Matrix(rows), &Value::Number(power)) => {
+            if rows.len() != rows[0].len() || power.fract() != 0.0 {
+                return Err(String::from("Only a square matrix can be raised to a power, and only a whole one."));
+            }
+            if power.abs() > MAX_MATRIX_POWER {
+                return Err(format!("A matrix can only be raised to a power up to {}.", MAX_MATRIX_POWER));
+            }
+            // A negative power raises the inverse.
+            let mut base = if power < 0.0 { apply("inv", vec![left.clone()])? } else { left.clone() };
+            let identity = (0..rows.len())
+                .map(|row| (0..rows.len()).map(|column| if row == column { 1.0 } else { 0.0 }).collect())
+                .collect();
+            let mut ret = Value::Matrix(identity);
+            // Squaring for each bit of the power takes a few dozen products at most.
+            let mut power = power.abs() as u64;
+            while power > 0 {
+                if power % 2 == 1 {
+                    ret = operate("*", ret, base.clone())?;
+                }
+                power /= 2;
+                if power > 0 {
+                    base = operate("*", base.clone(), base)?;
+                }
+            }
+            return Ok(ret);
+        }
+        (_, &Value::Matrix(_), &Value::Vector(_)) | (_, &Value::Vector(_), &Value::Matrix(_)) => {
+            return Err(shape_error(op, &left, &right));
+        }
+        (_, &Value::Number(a), _) => {
+            return map(&right, |b| calculator::process_function(op, &[a, b]));
+        }
+        (_, _, &Value::Number(b)) => {
+            return map(&left, |a| calculator::process_function(op, &[a, b]));
+        }
+        (_, Value::Vector(a), Value::Vector(b)) if a.len() == b.len() => {
+            let mut values = Vec::<f64>::new();
+            for (a, b) in a.iter().zip(b.iter()) {
+                values.push(calculator::process_function(op, &[*a, *b])?);
+            }
+            return Ok(Value::Vector(values));
+        }
+        (_, Value::Matrix(a), Value::Matrix(b)) if a.len() == b.len() && a[0].len() == b[0].len() => {
+            let mut rows = Vec::<Vec<f64>>::new();
+            for (a, b) in a.iter().zip(b.iter()) {
+                let mut row = Vec::<f64>::new();
+                for (a, b) in a.iter().zip(b.iter()) {
+                    row.push(calculator::process_function(op, &[*a, *b])?);
+                }
+                rows.push(row);
+            }
+            return Ok(Value::Matrix(rows));
+        }
+        _ => {
+            return Err(shape_error(op, &left, &right));
+        }
+    }
+}
+
+/// Private function that applies a function of one number to every element of a value.
+fn map<F>(value: &Value, function: F) -> Result<Value, String>
+where
+    F: Fn(f64) -> Result<f64, String>,
+{
+    match *value {
+        Value::Number(val) => return Ok(Value::Number(function(val)?)),
+        Value::Boolean(_) | Value::Date(_) | Value::Duration(_) => {
+            return Err(format!("Expected a number, not {}.", value.describe()));
+        }
+        Value::Vector(ref values) => {
+            let mut ret = Vec::<f64>::new();
+            for val in values.iter() {
+                ret.push(function(*val)?);
+            }
+            return Ok(Value::Vector(ret));
+        }
+        Value::Matrix(ref rows) => {
+            let mut ret = Vec::<Vec<f64>>::new();
+            for row in rows.iter() {
+                let mut values = Vec::<f64>::new();
+                for val in row.iter() {
+                    values.push(function(*val)?);
+                }
+                ret.push(values);
+            }
+            return Ok(Value::Matrix(ret));
+        }
+    }
+}
+
+/// Private function that picks an element of a vector, or a row or element of a matrix. The
+/// indexes count from 0.
+fn index(value: &Value, indexes: &[usize]) -> Result<Value, String> {
+    let out_of_range = |position: usize| format!("Index {} is out of range for {}.", position + 1, value.describe());
+    match (value, indexes) {
+        (Value::Vector(values), [position]) => match values.get(*position) {
+            Some(val) => return Ok(Value::Number(*val)),
+            None => return Err(out_of_range(*position)),
+        },
+        (Value::Matrix(rows), [row]) => match rows.get(*row) {
+            Some(values) => return Ok(Value::Vector(values.clone())),
+            None => return Err(out_of_range(*row)),
+        },
+        (Value::Matrix(rows), [row, column]) => match rows.get(*row) {
+            Some(values) => match values.get(*column) {
+                Some(val) => return Ok(Value::Number(*val)),
+                None => return Err(out_of_range(*column)),
+            },
+            None => return Err(out_of_range(*row)),
+        },
+        _ => {
+            return Err(format!("Cannot index {} with {} index(es).", value.describe(), indexes.len()));
+        }
+    }
+}
+
+fn transpose(rows: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    return (0..rows[0].len())
+        .map(|column| rows.iter().map(|row| row[column]).collect())
+        .collect();
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    return a.iter().zip(b.iter()).map(|(a, b)| a * b).sum();
+}
+
+fn shape_error(op: &str, left: &Value, right: &Value) -> String {
+    return format!("Cannot apply {} to {} and {}.", op, left.describe(), right.describe());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prefix;
+
+    fn run(input: &str) -> Result<String, String> {
+        let vec = calculator::parse_to_vec(&prefix::translate_infix(input)?)?;
+        return calculate(&vec, &HashMap::<String, f64>::new()).map(|value| print_value(&value));
+    }
+
+    fn check(result: &str, input: &str) {
+        assert_eq!(Ok(String::from(result)), run(input));
+    }
+
+    #[test]
+    fn test_literals() {
+        check("[1, 2, 3]", "[1, 2, 3]");
+        check("[[1, 2], [3, 4]]", "[[1, 2], [3, 4]]");
+        check("[2, 3]", "[1 + 1, sqrt(9)]");
+        check("3.00000000", "1 + 2");
+    }
+
+    #[test]
+    fn test_element_wise() {
+        check("[5, 7, 9]", "[1, 2, 3] + [4, 5, 6]");
+        check("[2, 4, 6]", "2 * [1, 2, 3]");
+        check("[0.5, 1]", "[1, 2] / 2");
+        check("[4, 10, 18]", "[1, 2, 3] * [4, 5, 6]");
+        check("[-1, -2]", "-[1, 2]");
+        check("[1, 2, 3]", "sqrt([1, 4, 9])");
+        check("[[2, 3], [4, 5]]", "[[1, 2], [3, 4]] + 1");
+        check("[0.3333333333, 0]", "[1 / 3, -0]");
+    }
+
+    #[test]
+    fn test_matrix_arithmetic() {
+        check("[[19, 22], [43, 50]]", "[[1, 2], [3, 4]] * [[5, 6], [7, 8]]");
+        check("[5, 11]", "[[1, 2], [3, 4]] * [1, 2]");
+        check("[7, 10]", "[1, 2] * [[1, 2], [3, 4]]");
+        check("[[7, 10], [15, 22]]", "[[1, 2], [3, 4]]^2");
+        check("[[37, 54], [81, 118]]", "[[1, 2], [3, 4]]^3");
+        check("[[1, 0], [0, 1]]", "[[1, 2], [3, 4]]^0");
+        check("[[1, 1000000000], [0, 1]]", "[[1, 1], [0, 1]]^1e9");
+        check("[[1, -3], [0, 1]]", "[[1, 1], [0, 1]]^-3");
+        assert!(run("[[1, 2], [3, 4]]^1e10").is_err());
+    }
+
+    #[test]
+    fn test_functions() {
+        check("32.00000000", "dot([1, 2, 3], [4, 5, 6])");
+        check("[0, 0, 1]", "cross([1, 0, 0], [0, 1, 0])");
+        check("[[1, 3], [2, 4]]", "transpose([[1, 2], [3, 4]])");
+        check("[[1], [2]]", "transpose([1, 2])");
+        check("-2.00000000", "det([[1, 2], [3, 4]])");
+        check("[[-2, 1], [1.5, -0.5]]", "inv([[1, 2], [3, 4]])");
+    }
+
+    #[test]
+    fn test_indexing() {
+        check("2.00000000", "index([1, 2, 3], 2)");
+        check("[3, 4]", "index([[1, 2], [3, 4]], 2)");
+        check("3.00000000", "index([[1, 2], [3, 4]], 2, 1)");
+        check("3.00000000", "[1, 2, 3][3]");
+        check("4.00000000", "[[1, 2], [3, 4]][2][2]");
+    }
+
+    #[test]
+    fn test_statistics() {
+        check("3.00000000", "mean([1, 2, 3, 4, 5])");
+        check("2.50000000", "median([4, 1], 3, 2)");
+        check("6.00000000", "sum([1, 2, 3])");
+        check("14.00000000", "sum(k^2, k, 1, 3)");
+        check("4.00000000", "count([[1, 2], [3, 4]])");
+        check("5.00000000", "max([1, 5, 2])");
+        check("9.00000000", "percentile([1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 90) - 0.1");
+    }
+
+    #[test]
+    fn test_named_lists() {
+        let mut lists = HashMap::<String, Vec<f64>>::new();
+        lists.insert(String::from("data"), vec![2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        let mut numbers = HashMap::<String, f64>::new();
+        numbers.insert(String::from("n"), 2.0);
+        let run = |input: &str| {
+            let vec = calculator::parse_to_vec(&prefix::translate_infix(input).unwrap()).unwrap();
+            let variables = integrate::Variables { numbers: &numbers, lists: &lists, now: None };
+            return calculate_with(&vec, &variables).map(|value| print_value(&value));
+        };
+        assert_eq!(Ok(String::from("2.00000000")), run("pstdev(data)"));
+        assert_eq!(Ok(String::from("4.00000000")), run("mode(data) * n / 2"));
+        assert_eq!(Ok(String::from("[4, 16]")), run("[data[1], data[8] - 1] * n"));
+        assert_eq!(Ok(String::from("3.00000000")), run("n + 1"));
+    }
+
+    #[test]
+    fn test_shape_errors() {
+        assert_eq!(
+            Err(String::from("Cannot apply + to a vector of length 2 and a vector of length 3.")),
+            run("[1, 2] + [1, 2, 3]")
+        );
+        assert_eq!(
+            Err(String::from("Cannot apply * to a 2 x 2 matrix and a vector of length 3.")),
+            run("[[1, 2], [3, 4]] * [1, 2, 3]")
+        );
+        assert_eq!(Err(String::from("Every row of a matrix must have the same length.")), run("[[1, 2], [3, 4, 5]]"));
+        assert_eq!(Err(String::from("Index 4 is out of range for a vector of length 3.")), run("[1, 2, 3][4]"));
+        assert_eq!(Err(String::from("Matrix is singular, so it has no inverse.")), run("inv([[1, 2], [2, 4]])"));
+        assert_eq!(
+            Err(String::from("Function det needs a square matrix, not a vector of length 2.")),
+            run("det([1, 2])")
+        );
+        assert!(run("dot([1, 2], [[1, 2], [3, 4]])").is_err());
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(Ok(String::from("true")), run("1 + 1 == 2"));
+        assert_eq!(Ok(String::from("false")), run("3 < 2"));
+        assert_eq!(Ok(String::from("true")), run("2 <= 2 and 3 >= 4 or 1 != 2"));
+        assert_eq!(Ok(String::from("true")), run("not 3 > 4"));
+        assert_eq!(Ok(String::from("true")), run("(1 < 2) == true"));
+    }
+
+    #[test]
+    fn test_conditionals() {
+        assert_eq!(Ok(String::from("3.00000000")), run("if(-3 < 0, 3, -3)"));
+        assert_eq!(Ok(String::from("2.00000000")), run("0 ? 1 : 2"));
+        assert_eq!(Ok(String::from("[1, 2]")), run("true ? [1, 2] : 0"));
+        // The branch not taken is never evaluated.
+        assert_eq!(Ok(String::from("1.00000000")), run("1 > 0 ? 1 : undefined"));
+        assert_eq!(Ok(String::from("false")), run("false and undefined"));
+        assert_eq!(Ok(String::from("4.00000000")), run("sum(k > 2 ? 1 : 0, k, 1, 6)"));
+    }
+
+    #[test]
+    fn test_boolean_errors() {
+        assert_eq!(Err(String::from("Function + needs numbers, not a boolean.")), run("true + 1"));
+        assert_eq!(Err(String::from("Expected true or false, not a vector of length 2.")), run("if([1, 2], 1, 0)"));
+        assert_eq!(Err(String::from("Cannot compare a boolean with a number using <.")), run("true < 1"));
+        let vec = calculator::parse_to_vec(&prefix::translate_infix("1 < 2").unwrap()).unwrap();
+        assert_eq!(Err(String::from("Expected a number, not a boolean.")), calculator::calculate(&vec, &HashMap::new()).map(|_| ()));
+    }
+
+    #[test]
+    fn test_dates() {
+        let (numbers, lists) = (HashMap::<String, f64>::new(), HashMap::<String, Vec<f64>>::new());
+        let now = date::parse_date("2026-10-18T09:30");
+        let run = |input: &str| {
+            let vec = calculator::parse_to_vec(&prefix::translate_infix(input).unwrap()).unwrap();
+            let variables = integrate::Variables { numbers: &numbers, lists: &lists, now };
+            return calculate_with(&vec, &variables).map(|value| print_value(&value));
+        };
+        assert_eq!(Ok(String::from("44.00000000")), run("2026-12-01 - 2026-10-18 in days"));
+        assert_eq!(Ok(String::from("44 days")), run("2026-12-01 - 2026-10-18"));
+        assert_eq!(Ok(String::from("2026-11-08T09:30")), run("now + 3 weeks"));
+        assert_eq!(Ok(String::from("2026-10-17T23:00")), run("2026-10-18 - 1 hour"));
+        assert_eq!(Ok(String::from("1.50000000")), run("90 minutes in hours"));
+        assert_eq!(Ok(String::from("1 day 4 hours 30 minutes")), run("(2 days + 5 hours) / 2 + 2 hours"));
+        assert_eq!(Ok(String::from("true")), run("now < 2026-12-01 and 2 days > 36 hours"));
+        assert_eq!(Ok(String::from("3 days")), run("2026-12-01 - now > 7 days ? 3 days : 0 days"));
+    }
+
+    #[test]
+    fn test_date_errors() {
+        assert_eq!(Err(String::from("The current time is not available.")), run("now + 1 day"));
+        assert_eq!(Err(String::from("Cannot apply + to a date and a date.")), run("2026-12-01 + 2026-10-18"));
+        assert_eq!(Err(String::from("Cannot apply * to a duration and a duration.")), run("2 days * 3 days"));
+        assert_eq!(
+            Err(String::from("Cannot convert a number with in; use it on a duration, e.g. 90 minutes in hours.")),
+            run("90 in hours")
+        );
+        assert_eq!(Err(String::from("Function sqrt needs numbers, not a duration.")), run("sqrt(4 days)"));
+        assert_eq!(Err(String::from("Cannot compare a date with a duration using <.")), run("2026-12-01 < 3 days"));
+        let range = Err(String::from("The date is out of range; dates run from 0000-01-01 to 9999-12-31."));
+        assert_eq!(range, run("2026-01-01 + 1e300 days"));
+        assert_eq!(range, run("2026-01-01 - 0 / 0 * 1 day"));
+        assert_eq!(range, run("0000-01-01 - 1 second"));
+        assert_eq!(Ok(String::from("9999-12-31T23:59:59")), run("9999-12-31 + 86399 seconds"));
+    }
+
+    #[test]
+    fn test_scalar_equations_unchanged() {
+        let vec = calculator::parse_to_vec("+ 1 2").unwrap();
+        assert!(!needs_tree(&vec));
+        assert_eq!(Err(String::from("Expected a number, not a vector of length 2.")), calculator::calculate(&calculator::parse_to_vec("( list 1 2 )").unwrap(), &HashMap::new()).map(|_| ()));
+    }
+}
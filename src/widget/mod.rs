@@ -0,0 +1,706 @@
+#![allow(clippy::needless_return)]
+
+use complete::Completion;
+use highlight;
+use keypad::{self, Bindings, KeyAction, Layout};
+use show_entry;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Document, Element, Event, EventTarget, HtmlInputElement, HtmlTextAreaElement, KeyboardEvent};
+use Calc;
+use Entry;
+use validate;
+use Notation;
+
+/// Milliseconds of quiet typing after which the answer is previewed.
+const PREVIEW_DELAY: i32 = 200;
+
+/// Struct that holds a calculator built inside a page element by `Calc::mount`, together with
+/// the event handlers that drive it. Dropping it or calling `unmount` takes it off the page.
+#[wasm_bindgen]
+pub struct Widget {
+    calc: Rc<RefCell<Calc>>,
+    root: Option<Element>,
+    keypad: Element,
+    layout: Rc<RefCell<Layout>>,
+    bindings: Rc<RefCell<Bindings>>,
+    live_preview: Rc<Cell<bool>>,
+    view: View,
+    listeners: Vec<Listener>,
+}
+
+/// Struct that remembers an event handler so that it can be removed again.
+struct Listener {
+    target: EventTarget,
+    event: &'static str,
+    closure: Closure<dyn FnMut(Event)>,
+}
+
+/// Struct that holds the elements of a mounted calculator.
+#[derive(Clone)]
+struct View {
+    input: HtmlInputElement,
+    output: HtmlInputElement,
+    history: HtmlTextAreaElement,
+    /// Copy of the input with its pieces highlighted.
+    expression: Element,
+    /// Handle of the timer that will show a preview, if one is waiting.
+    pending: Rc<Cell<Option<i32>>>,
+    /// List of names that complete the one being typed.
+    menu: Element,
+    completions: Rc<RefCell<Completions>>,
+    autocomplete: Rc<Cell<bool>>,
+    /// Table of the variables that are set, whose names and values can be edited.
+    panel: Element,
+    show_panel: Rc<Cell<bool>>,
+}
+
+/// Struct that holds the completions on offer and which of them Enter or Tab would take.
+#[derive(Default)]
+struct Completions {
+    items: Vec<Completion>,
+    selected: usize,
+}
+
+#[wasm_bindgen]
+impl Widget {
+    #[wasm_bindgen(setter)]
+    pub fn set_notation(&mut self, notation: Notation) {
+        self.calc.borrow_mut().set_notation(notation);
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_stack_mode(&mut self, stack_mode: bool) {
+        self.calc.borrow_mut().set_stack_mode(stack_mode);
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_store_solutions(&mut self, store_solutions: bool) {
+        self.calc.borrow_mut().set_store_solutions(store_solutions);
+    }
+
+    /// Replaces the keypad with another layout: the name of a built-in one (basic, scientific or
+    /// programmer) or a layout written in JSON, as described by `keypad::Layout`.
+    #[wasm_bindgen]
+    pub fn set_keypad(&mut self, layout: &str) -> Result<(), JsValue> {
+        let layout = match keypad::builtin_layout(layout) {
+            Some(layout) => layout,
+            None => keypad::parse_layout(layout).map_err(|e| JsValue::from_str(&e))?,
+        };
+        render_keypad(&self.keypad, &layout)?;
+        *self.layout.borrow_mut() = layout;
+        return Ok(());
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn live_preview(&self) -> bool {
+        return self.live_preview.get();
+    }
+
+    /// Selects whether the answer box previews the answer, greyed, or the error while the input
+    /// is typed. Previews set no variables and add nothing to the history.
+    #[wasm_bindgen(setter)]
+    pub fn set_live_preview(&mut self, live_preview: bool) {
+        self.live_preview.set(live_preview);
+        if !live_preview {
+            self.view.cancel_preview();
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn autocomplete(&self) -> bool {
+        return self.view.autocomplete.get();
+    }
+
+    /// Selects whether a list of the variables, constants and functions that complete the name
+    /// being typed drops down under the input. The arrow keys pick one, Enter or Tab or a click
+    /// takes it and Escape closes the list.
+    #[wasm_bindgen(setter)]
+    pub fn set_autocomplete(&mut self, autocomplete: bool) {
+        self.view.autocomplete.set(autocomplete);
+        if !autocomplete {
+            report(self.view.show_completions(Vec::new()));
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn variables_panel(&self) -> bool {
+        return self.view.show_panel.get();
+    }
+
+    /// Selects whether the variables that are set are listed under the keypad. A value can be
+    /// changed there by typing an equation, which recalculates the current equation; a name can
+    /// be changed or a variable removed, and the empty row at the end adds one.
+    #[wasm_bindgen(setter)]
+    pub fn set_variables_panel(&mut self, variables_panel: bool) {
+        self.view.show_panel.set(variables_panel);
+        report(self.view.show_variables(&self.calc));
+    }
+
+    /// Makes a keyboard shortcut in the input, e.g. Ctrl+l, run an action: evaluate, clear,
+    /// backspace, previous or next. Enter, Escape and the up and down arrows are bound to start
+    /// with.
+    #[wasm_bindgen]
+    pub fn bind_key(&mut self, shortcut: &str, action: &str) -> Result<(), JsValue> {
+        return self.bindings.borrow_mut().bind(shortcut, action).map_err(|e| JsValue::from_str(&e));
+    }
+
+    /// Gives a shortcut back to the input.
+    #[wasm_bindgen]
+    pub fn unbind_key(&mut self, shortcut: &str) -> Result<(), JsValue> {
+        return self.bindings.borrow_mut().unbind(shortcut).map_err(|e| JsValue::from_str(&e));
+    }
+
+    /// Removes the calculator and its event handlers from the page. Calling it again does
+    /// nothing.
+    #[wasm_bindgen]
+    pub fn unmount(&mut self) {
+        self.view.cancel_preview();
+        for listener in self.listeners.drain(..) {
+            let callback = listener.closure.as_ref().unchecked_ref();
+            // The target may already have left the page, which is fine.
+            let _ = listener.target.remove_event_listener_with_callback(listener.event, callback);
+        }
+        if let Some(root) = self.root.take() {
+            root.remove();
+        }
+    }
+}
+
+impl Widget {
+    /// Function that builds the calculator's elements at the end of `container` and wires up
+    /// the keyboard shortcuts and the keypad.
+    pub fn mount(calc: Calc, container: &Element) -> Result<Widget, JsValue> {
+        let document = container
+            .owner_document()
+            .ok_or_else(|| JsValue::from_str("The container is not part of a document."))?;
+        let root = create(&document, "div", "webcalc")?;
+        let output = create(&document, "input", "webcalc-output")?.unchecked_into::<HtmlInputElement>();
+        output.set_read_only(true);
+        let history = create(&document, "textarea", "webcalc-history")?.unchecked_into::<HtmlTextAreaElement>();
+        history.set_read_only(true);
+        history.set_rows(8);
+        let input = create(&document, "input", "webcalc-input")?.unchecked_into::<HtmlInputElement>();
+        input.set_attribute("aria-label", "Equation")?;
+        let menu = create(&document, "ul", "webcalc-completions")?;
+        menu.set_attribute("hidden", "")?;
+        menu.set_attribute("role", "listbox")?;
+        let expression = create(&document, "div", "webcalc-expression")?;
+        expression.set_attribute("aria-hidden", "true")?;
+        let keypad = create(&document, "div", "webcalc-keypad")?;
+        let layout = keypad::builtin_layout("basic").unwrap();
+        render_keypad(&keypad, &layout)?;
+        let panel = create(&document, "div", "webcalc-variables")?;
+        panel.set_attribute("hidden", "")?;
+        for child in [output.as_ref(), history.as_ref(), input.as_ref(), &menu, &expression, &keypad, &panel].iter() {
+            root.append_child(child)?;
+        }
+        container.append_child(&root)?;
+
+        let view = View {
+            input,
+            output,
+            history,
+            expression,
+            pending: Rc::new(Cell::new(None)),
+            menu,
+            completions: Rc::new(RefCell::new(Completions::default())),
+            autocomplete: Rc::new(Cell::new(false)),
+            panel,
+            show_panel: Rc::new(Cell::new(false)),
+        };
+        let mut ret = Widget {
+            calc: Rc::new(RefCell::new(calc)),
+            root: Some(root),
+            keypad: keypad.clone(),
+            layout: Rc::new(RefCell::new(layout)),
+            bindings: Rc::new(RefCell::new(Bindings::new())),
+            live_preview: Rc::new(Cell::new(true)),
+            view: view.clone(),
+            listeners: Vec::new(),
+        };
+        let (shortcut_view, shortcut_calc, bindings) = (view.clone(), ret.calc.clone(), ret.bindings.clone());
+        ret.listen(view.input.as_ref(), "keydown", move |event| {
+            if let Some(event) = event.dyn_ref::<KeyboardEvent>() {
+                // An open list of completions has first claim on the keys that move through it.
+                match shortcut_view.choose(&event.key()) {
+                    Ok(true) => {
+                        event.prevent_default();
+                        return;
+                    }
+                    Ok(false) => {}
+                    Err(e) => return report(Err(e)),
+                }
+                // The borrow ends before pressing, as `report` throws without unwinding.
+                let action = bindings.borrow().action(&event.key(), event.ctrl_key(), event.alt_key(), event.shift_key(), event.meta_key()).cloned();
+                if let Some(action) = action {
+                    event.prevent_default();
+                    report(shortcut_view.press(&action, &shortcut_calc));
+                }
+            }
+        })?;
+        let (preview_view, preview_calc) = (view.clone(), ret.calc.clone());
+        let show_preview = Rc::new(Closure::wrap(Box::new(move || {
+            preview_view.pending.set(None);
+            report(preview_view.show_preview(&preview_calc));
+        }) as Box<dyn FnMut()>));
+        let (typing_view, live_preview) = (view.clone(), ret.live_preview.clone());
+        ret.listen(view.input.as_ref(), "input", move |_| {
+            if live_preview.get() {
+                report(typing_view.schedule_preview(&show_preview));
+            }
+        })?;
+        // The caret moves with keys and clicks as well as typing, and the matching bracket with it.
+        for event in ["input", "keyup", "click"].iter() {
+            let (highlight_view, highlight_calc) = (view.clone(), ret.calc.clone());
+            ret.listen(view.input.as_ref(), event, move |_| {
+                report(highlight_view.show_expression(&highlight_calc));
+            })?;
+        }
+        let (complete_view, complete_calc) = (view.clone(), ret.calc.clone());
+        ret.listen(view.input.as_ref(), "input", move |_| {
+            if complete_view.autocomplete.get() {
+                let value = complete_view.input.value();
+                let caret = complete_view.caret();
+                report(caret.and_then(|caret| complete_view.show_completions(complete_calc.borrow().completions(&value, caret))));
+            }
+        })?;
+        let menu_view = view.clone();
+        ret.listen(view.menu.as_ref(), "click", move |event| {
+            let index = event
+                .target()
+                .and_then(|target| target.dyn_into::<Element>().ok())
+                .and_then(|target| target.closest("[data-index]").ok().flatten())
+                .and_then(|item| item.get_attribute("data-index"))
+                .and_then(|index| index.parse::<usize>().ok());
+            if let Some(index) = index {
+                report(menu_view.accept(index).and_then(|_| menu_view.input.focus()));
+            }
+        })?;
+        let (change_view, change_calc) = (view.clone(), ret.calc.clone());
+        ret.listen(view.panel.as_ref(), "change", move |event| {
+            if let Some(field) = event.target().and_then(|target| target.dyn_into::<HtmlInputElement>().ok()) {
+                report(change_view.edit_variable(&field, &change_calc));
+            }
+        })?;
+        let (delete_view, delete_calc) = (view.clone(), ret.calc.clone());
+        ret.listen(view.panel.as_ref(), "click", move |event| {
+            let name = event
+                .target()
+                .and_then(|target| target.dyn_into::<Element>().ok())
+                .and_then(|target| target.get_attribute("data-delete"));
+            if let Some(name) = name {
+                delete_calc.borrow_mut().delete_variable(&name);
+                report(delete_view.variables_changed(&format!("delete {}", name), &delete_calc));
+            }
+        })?;
+        let (key_view, key_calc, key_layout) = (view.clone(), ret.calc.clone(), ret.layout.clone());
+        ret.listen(keypad.as_ref(), "click", move |event| {
+            let index = event
+                .target()
+                .and_then(|target| target.dyn_into::<Element>().ok())
+                .and_then(|target| target.get_attribute("data-key"))
+                .and_then(|index| index.parse::<usize>().ok());
+            // The borrow ends before pressing, as `report` throws without unwinding.
+            let key = index.and_then(|index| key_layout.borrow().keys().get(index).map(|key| (*key).clone()));
+            if let Some(key) = key {
+                report(key_view.press(&key.action, &key_calc));
+            }
+        })?;
+        return Ok(ret);
+    }
+
+    /// Adds an event handler that lives as long as the widget.
+    fn listen<F>(&mut self, target: &EventTarget, event: &'static str, handler: F) -> Result<(), JsValue>
+    where
+        F: FnMut(Event) + 'static,
+    {
+        let closure = Closure::wrap(Box::new(handler) as Box<dyn FnMut(Event)>);
+        target.add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())?;
+        self.listeners.push(Listener { target: target.clone(), event, closure });
+        return Ok(());
+    }
+}
+
+impl Drop for Widget {
+    fn drop(&mut self) {
+        self.unmount();
+    }
+}
+
+impl View {
+    /// Evaluates the input and shows the answer, as `Calc::calc` does.
+    fn run(&self, calc: &RefCell<Calc>) -> Result<(), JsValue> {
+        return self.run_text(&self.input.value(), calc);
+    }
+
+    /// Evaluates a line of input and shows the answer.
+    fn run_text(&self, text: &str, calc: &RefCell<Calc>) -> Result<(), JsValue> {
+        self.cancel_preview();
+        self.output.set_class_name("webcalc-output");
+        let result = calc.borrow_mut().evaluate(text);
+        show_entry(&self.output, &self.history, result)?;
+        return self.show_variables(calc);
+    }
+
+    /// Lists the variables in the panel, one row each with its name, its value and a button to
+    /// remove it, and an empty row to add one. Does nothing but hide the panel when it is off.
+    fn show_variables(&self, calc: &RefCell<Calc>) -> Result<(), JsValue> {
+        self.panel.set_inner_html("");
+        if !self.show_panel.get() {
+            return self.panel.set_attribute("hidden", "");
+        }
+        self.panel.remove_attribute("hidden")?;
+        let document = self
+            .panel
+            .owner_document()
+            .ok_or_else(|| JsValue::from_str("The calculator is not part of a document."))?;
+        let mut rows = calc.borrow().variables().iter().map(|variable| (variable.name(), variable.value(), variable.kind())).collect::<Vec<_>>();
+        rows.push((String::new(), String::new(), String::from("new")));
+        for (name, value, kind) in rows.iter() {
+            let row = create(&document, "div", &format!("webcalc-variable webcalc-{}", kind))?;
+            for (field, text) in [("name", name), ("value", value)].iter() {
+                let input = create(&document, "input", &format!("webcalc-variable-{}", field))?.unchecked_into::<HtmlInputElement>();
+                input.set_value(text);
+                input.set_attribute("data-name", name)?;
+                input.set_attribute("data-field", field)?;
+                input.set_attribute("aria-label", &format!("Variable {}", field))?;
+                row.append_child(&input)?;
+            }
+            if !name.is_empty() {
+                let button = create(&document, "button", "webcalc-delete")?;
+                button.set_attribute("type", "button")?;
+                button.set_attribute("data-delete", name)?;
+                button.set_attribute("aria-label", &format!("Delete {}", name))?;
+                button.set_text_content(Some("×"));
+                row.append_child(&button)?;
+            }
+            self.panel.append_child(&row)?;
+        }
+        return Ok(());
+    }
+
+    /// Applies an edit made in the panel: a new value sets the variable, a new name renames it.
+    /// A value that cannot be used marks the field with the error instead.
+    fn edit_variable(&self, field: &HtmlInputElement, calc: &RefCell<Calc>) -> Result<(), JsValue> {
+        let name = field.get_attribute("data-name").unwrap_or_default();
+        let text = field.value();
+        let (result, note) = match field.get_attribute("data-field").as_deref() {
+            Some("value") => {
+                // The empty row takes its name from the field next to it.
+                let name = match name.as_str() {
+                    "" => field
+                        .closest(".webcalc-variable")?
+                        .and_then(|row| row.query_selector(".webcalc-variable-name").ok().flatten())
+                        .and_then(|input| input.dyn_into::<HtmlInputElement>().ok())
+                        .map(|input| input.value().trim().to_string())
+                        .unwrap_or_default(),
+                    _ => name,
+                };
+                (calc.borrow_mut().assign_variable(&name, &text), format!("{} = {}", name, text.trim()))
+            }
+            Some("name") if !name.is_empty() => (calc.borrow_mut().rename(&name, text.trim()), format!("rename {} {}", name, text.trim())),
+            _ => return Ok(()),
+        };
+        match result {
+            Ok(()) => return self.variables_changed(&note, calc),
+            Err(e) => {
+                field.set_class_name(&format!("{} webcalc-error", field.class_name()));
+                return field.set_attribute("title", &e);
+            }
+        }
+    }
+
+    /// Notes a change made in the panel in the history, shows the current equation calculated
+    /// again and lists the variables anew.
+    fn variables_changed(&self, note: &str, calc: &RefCell<Calc>) -> Result<(), JsValue> {
+        let result = calc.borrow().recalculate();
+        if let Some(result) = result {
+            let result = result.map(|answer| Entry { answer, history: format!("{}\n", note) });
+            show_entry(&self.output, &self.history, result)?;
+        }
+        return self.show_variables(calc);
+    }
+
+    /// Shows the preview `callback` once the input has been left alone for `PREVIEW_DELAY`,
+    /// putting off any preview still waiting.
+    fn schedule_preview(&self, callback: &Closure<dyn FnMut()>) -> Result<(), JsValue> {
+        self.cancel_preview();
+        let window = web_sys::window().ok_or_else(|| JsValue::from_str("No window to time the preview with."))?;
+        let handle = window.set_timeout_with_callback_and_timeout_and_arguments_0(callback.as_ref().unchecked_ref(), PREVIEW_DELAY)?;
+        self.pending.set(Some(handle));
+        return Ok(());
+    }
+
+    /// Forgets a preview that is still waiting to be shown.
+    fn cancel_preview(&self) {
+        if let (Some(handle), Some(window)) = (self.pending.take(), web_sys::window()) {
+            window.clear_timeout_with_handle(handle);
+        }
+    }
+
+    /// Shows in the answer box what the input would give, or the first error it raises, marked
+    /// as a preview. An empty input leaves the answer box alone.
+    fn show_preview(&self, calc: &RefCell<Calc>) -> Result<(), JsValue> {
+        let input = self.input.value();
+        if input.trim().is_empty() {
+            return Ok(());
+        }
+        match calc.borrow().dry_run(&input) {
+            Ok(entry) => {
+                self.output.set_class_name("webcalc-output webcalc-preview");
+                self.output.set_value(&entry.answer);
+            }
+            Err(e) => {
+                // The answer box is one line, so the error's lines are joined.
+                let message = e.lines().map(|line| line.trim()).filter(|line| !line.is_empty()).collect::<Vec<&str>>();
+                self.output.set_class_name("webcalc-output webcalc-preview webcalc-error");
+                self.output.set_value(&message.join(" "));
+            }
+        }
+        return Ok(());
+    }
+
+    /// Shows the input with every piece in a class of its kind, the span of the first error
+    /// underlined with its message as a tooltip, and the bracket matching the one at the caret
+    /// marked.
+    fn show_expression(&self, calc: &RefCell<Calc>) -> Result<(), JsValue> {
+        let document = self
+            .expression
+            .owner_document()
+            .ok_or_else(|| JsValue::from_str("The calculator is not part of a document."))?;
+        let value = self.input.value();
+        let chars = value.chars().collect::<Vec<char>>();
+        let error = calc.borrow().syntax_error(&value);
+        let caret = self.caret()?;
+        let brackets = validate::matching_bracket(&value, caret);
+        self.expression.set_inner_html("");
+        let mut position = 0;
+        for span in highlight::highlight(&value, error.as_ref()).iter() {
+            if span.start > position {
+                let gap = create(&document, "span", "")?;
+                gap.set_text_content(Some(&chars[position..span.start].iter().collect::<String>()));
+                self.expression.append_child(&gap)?;
+            }
+            let mut class_name = format!("webcalc-token webcalc-{}", span.class.name());
+            if brackets.is_some_and(|(open, close)| span.start == open || span.start == close) {
+                class_name.push_str(" webcalc-match");
+            }
+            let piece = create(&document, "span", &class_name)?;
+            if span.start == span.end {
+                // An error at the end needs something to underline.
+                piece.set_text_content(Some("\u{a0}"));
+            } else {
+                piece.set_text_content(Some(&chars[span.start..span.end].iter().collect::<String>()));
+            }
+            if let (Some(error), highlight::Class::Error) = (error.as_ref(), span.class) {
+                piece.set_attribute("title", &error.message)?;
+            }
+            self.expression.append_child(&piece)?;
+            position = span.end;
+        }
+        return Ok(());
+    }
+
+    /// Returns the position of the caret in the input, counted in characters.
+    fn caret(&self) -> Result<usize, JsValue> {
+        let value = self.input.value();
+        match self.input.selection_end()? {
+            Some(caret) => return Ok(char_position(&value, caret)),
+            None => return Ok(value.chars().count()),
+        }
+    }
+
+    /// Lists completions under the input, the first one picked, or hides the list when there
+    /// are none.
+    fn show_completions(&self, items: Vec<Completion>) -> Result<(), JsValue> {
+        if items.is_empty() {
+            self.menu.set_attribute("hidden", "")?;
+        } else {
+            self.menu.remove_attribute("hidden")?;
+        }
+        *self.completions.borrow_mut() = Completions { items, selected: 0 };
+        return self.render_completions();
+    }
+
+    fn render_completions(&self) -> Result<(), JsValue> {
+        let document = self
+            .menu
+            .owner_document()
+            .ok_or_else(|| JsValue::from_str("The calculator is not part of a document."))?;
+        self.menu.set_inner_html("");
+        let completions = self.completions.borrow();
+        for (index, completion) in completions.items.iter().enumerate() {
+            let mut class_name = format!("webcalc-completion webcalc-{}", completion.kind());
+            if index == completions.selected {
+                class_name.push_str(" webcalc-selected");
+            }
+            let item = create(&document, "li", &class_name)?;
+            item.set_attribute("role", "option")?;
+            item.set_attribute("data-index", &index.to_string())?;
+            let name = create(&document, "span", "webcalc-name")?;
+            name.set_text_content(Some(&completion.name()));
+            let detail = create(&document, "span", "webcalc-detail")?;
+            detail.set_text_content(Some(&completion.detail()));
+            item.append_child(&name)?;
+            item.append_child(&detail)?;
+            self.menu.append_child(&item)?;
+        }
+        return Ok(());
+    }
+
+    /// Moves through, takes from or closes the list of completions for a key pressed in the
+    /// input. Returns whether the key was used, which it is not when the list is closed.
+    fn choose(&self, key: &str) -> Result<bool, JsValue> {
+        let (count, selected) = {
+            let completions = self.completions.borrow();
+            (completions.items.len(), completions.selected)
+        };
+        if count == 0 {
+            return Ok(false);
+        }
+        match key {
+            "ArrowDown" => self.completions.borrow_mut().selected = (selected + 1) % count,
+            "ArrowUp" => self.completions.borrow_mut().selected = (selected + count - 1) % count,
+            "Enter" | "Tab" => {
+                self.accept(selected)?;
+                return Ok(true);
+            }
+            "Escape" => {
+                self.show_completions(Vec::new())?;
+                return Ok(true);
+            }
+            _ => return Ok(false),
+        }
+        self.render_completions()?;
+        return Ok(true);
+    }
+
+    /// Replaces the typed part of a name with a completion from the list.
+    fn accept(&self, index: usize) -> Result<(), JsValue> {
+        let completion = match self.completions.borrow().items.get(index) {
+            Some(completion) => completion.clone(),
+            None => return Ok(()),
+        };
+        self.show_completions(Vec::new())?;
+        let value = self.input.value();
+        let start = value.chars().take(completion.start()).map(|item| item.len_utf16() as u32).sum::<u32>();
+        let end = self.input.selection_end()?.unwrap_or(start).max(start);
+        self.input.set_selection_range(start, end)?;
+        return self.replace_selection(0, &completion.text());
+    }
+
+    /// Acts on a keypad key, then hands the focus back to the input.
+    fn press(&self, action: &KeyAction, calc: &RefCell<Calc>) -> Result<(), JsValue> {
+        match action {
+            KeyAction::Insert(text) => self.replace_selection(0, text)?,
+            KeyAction::Evaluate => self.run(calc)?,
+            KeyAction::Clear => {
+                self.input.set_value("");
+                self.changed()?;
+            }
+            KeyAction::Backspace => self.replace_selection(1, "")?,
+            KeyAction::Previous => {
+                let recalled = calc.borrow_mut().previous_input(&self.input.value());
+                self.recall(recalled)?;
+            }
+            KeyAction::Next => {
+                let recalled = calc.borrow_mut().next_input();
+                self.recall(recalled)?;
+            }
+            KeyAction::Command(command) => self.run_text(command, calc)?,
+        }
+        return self.input.focus();
+    }
+
+    /// Puts a recalled line in the input with the caret at its end; nothing changes when there
+    /// is no line to recall.
+    fn recall(&self, line: Option<String>) -> Result<(), JsValue> {
+        if let Some(line) = line {
+            let length = line.encode_utf16().count() as u32;
+            self.input.set_value(&line);
+            self.input.set_selection_range(length, length)?;
+            self.changed()?;
+        }
+        return Ok(());
+    }
+
+    /// Replaces the selected text of the input, together with `before` characters in front of
+    /// it when nothing is selected, and puts the caret after the new text.
+    fn replace_selection(&self, before: u32, text: &str) -> Result<(), JsValue> {
+        let value = self.input.value();
+        let length = value.encode_utf16().count() as u32;
+        let end = self.input.selection_end()?.unwrap_or(length);
+        let start = self.input.selection_start()?.unwrap_or(end);
+        let (value, caret) = keypad::replace_selection(&value, start, end, before, text);
+        self.input.set_value(&value);
+        self.input.set_selection_range(caret, caret)?;
+        return self.changed();
+    }
+
+    /// Tells the page that the input changed, as typing would, since setting its value does
+    /// not.
+    fn changed(&self) -> Result<(), JsValue> {
+        self.input.dispatch_event(&Event::new("input")?)?;
+        return Ok(());
+    }
+}
+
+/// Function that fills the keypad element with a button for every key of a layout, replacing
+/// what was there. Each button carries its position in `Layout::keys` so that one click handler
+/// serves them all.
+fn render_keypad(keypad: &Element, layout: &Layout) -> Result<(), JsValue> {
+    let document = keypad
+        .owner_document()
+        .ok_or_else(|| JsValue::from_str("The keypad is not part of a document."))?;
+    keypad.set_inner_html("");
+    keypad.set_attribute("data-layout", &layout.name)?;
+    let mut index = 0;
+    for row in layout.rows.iter() {
+        let line = create(&document, "div", "webcalc-row")?;
+        for key in row.iter() {
+            let class_name = match key.action {
+                KeyAction::Insert(_) => "webcalc-key",
+                KeyAction::Command(_) => "webcalc-key webcalc-command",
+                _ => "webcalc-key webcalc-action",
+            };
+            let button = create(&document, "button", class_name)?;
+            button.set_attribute("type", "button")?;
+            button.set_attribute("data-key", &index.to_string())?;
+            button.set_text_content(Some(&key.label));
+            line.append_child(&button)?;
+            index += 1;
+        }
+        keypad.append_child(&line)?;
+    }
+    return Ok(());
+}
+
+/// Function that turns a position in UTF-16 units, as the browser counts, into one in
+/// characters.
+fn char_position(value: &str, units: u32) -> usize {
+    let mut count = 0;
+    for (ret, item) in value.chars().enumerate() {
+        if count >= units as usize {
+            return ret;
+        }
+        count += item.len_utf16();
+    }
+    return value.chars().count();
+}
+
+/// Function that creates an element with a class name.
+fn create(document: &Document, tag: &str, class_name: &str) -> Result<Element, JsValue> {
+    let ret = document.create_element(tag)?;
+    ret.set_class_name(class_name);
+    return Ok(ret);
+}
+
+/// Function that reports an error from an event handler, which has no caller to return it to.
+fn report(result: Result<(), JsValue>) {
+    if let Err(e) = result {
+        wasm_bindgen::throw_val(e);
+    }
+}
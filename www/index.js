@@ -1,15 +1,29 @@
-import { Calc } from "webcalc"
+import { Calc, Notation } from "webcalc"
 
 if (process.env.NODE_ENV !== 'production') {
     console.log('Looks like we are in development mode!')
 }
     
-const calc = new Calc()
-const calcButton = document.getElementById("calcButton")
-calcButton.addEventListener("click", event => {
-    work()
+const calc = new Calc().mount(document.getElementById("calculator"))
+calc.autocomplete = true
+calc.variables_panel = true
+
+const notation = document.getElementById("notation")
+notation.addEventListener("change", event => {
+    calc.notation = Notation[notation.value]
 })
 
-const work = () => {
-    calc.calc()
-}
+const keypad = document.getElementById("keypad")
+keypad.addEventListener("change", event => {
+    calc.set_keypad(keypad.value)
+})
+
+const stackMode = document.getElementById("stackMode")
+stackMode.addEventListener("change", event => {
+    calc.stack_mode = stackMode.checked
+})
+
+const storeSolutions = document.getElementById("storeSolutions")
+storeSolutions.addEventListener("change", event => {
+    calc.store_solutions = storeSolutions.checked
+})